@@ -4,23 +4,66 @@ use std::{
 };
 
 use anyhow::{Context, Result};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
 pub struct AppConfig {
     pub entries: Vec<EntryConfig>,
     pub default_editor: Option<String>,
+    #[serde(default)]
+    pub git_backend: GitBackendKind,
+    #[serde(default)]
+    pub fetch: FetchConfig,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GitBackendKind {
+    /// Shell out to the `git` executable.
+    #[default]
+    Cli,
+    /// Read repositories in-process via gitoxide.
+    Gitoxide,
+}
+
+/// Background `git fetch --prune` while the TUI is open.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct FetchConfig {
+    pub enabled: bool,
+    /// Seconds between fetches of the same repository.
+    pub interval_secs: u64,
+    /// Minimum seconds between two fetches of any repositories, so that a
+    /// long list does not fetch everything at once.
+    pub spacing_secs: u64,
+}
+
+impl Default for FetchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 15 * 60,
+            spacing_secs: 10,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct EntryConfig {
     pub path: PathBuf,
     pub editor: Option<String>,
+    /// Branch to measure committed work against instead of `origin/HEAD`.
+    #[serde(default)]
+    pub default_branch: Option<String>,
+    /// Leave this entry out of background fetching.
+    #[serde(default)]
+    pub skip_fetch: bool,
 }
 
 const CONFIG_DIR: &str = "gmux";
 const LEGACY_CONFIG_DIR: &str = "quickswitch";
 const CONFIG_FILE_NAME: &str = "config.json";
+const CACHE_FILE_NAME: &str = "cache.json";
 
 pub fn load_config() -> Result<AppConfig> {
     let primary = config_file_path()?;
@@ -49,6 +92,33 @@ pub fn save_config(config: &AppConfig) -> Result<()> {
     Ok(())
 }
 
+/// Loads the status cache stored next to the config. A missing or unreadable
+/// cache is treated as empty since it can always be rebuilt.
+pub fn load_cache<T: DeserializeOwned + Default>() -> T {
+    cache_file_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_cache<T: Serialize>(cache: &T) -> Result<()> {
+    let path = cache_file_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create config directory {}", parent.display()))?;
+    }
+
+    let json = serde_json::to_string(cache)?;
+    fs::write(&path, json)
+        .with_context(|| format!("failed to write cache at {}", path.display()))?;
+    Ok(())
+}
+
+fn cache_file_path() -> Result<PathBuf> {
+    Ok(config_base_dir()?.join(CONFIG_DIR).join(CACHE_FILE_NAME))
+}
+
 fn config_file_path() -> Result<PathBuf> {
     Ok(config_base_dir()?.join(CONFIG_DIR).join(CONFIG_FILE_NAME))
 }
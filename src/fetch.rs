@@ -0,0 +1,328 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, TryRecvError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config::FetchConfig,
+    git::{self, unix_now},
+};
+
+/// Background fetch progress worth showing next to an entry. A successful
+/// fetch needs no state of its own: it shows up as a newer `FETCH_HEAD`.
+#[derive(Debug, Clone)]
+pub enum FetchState {
+    Fetching,
+    Failed {
+        /// Seconds since the Unix epoch.
+        at: i64,
+        message: String,
+    },
+}
+
+/// A repository the scheduler may fetch. Worktrees share their remotes, so
+/// repositories are identified by their common git directory.
+pub struct FetchTarget {
+    pub repo: PathBuf,
+    pub path: PathBuf,
+    pub last_fetch: Option<i64>,
+}
+
+struct RunningFetch {
+    repo: PathBuf,
+    outcome: mpsc::Receiver<Result<(), String>>,
+}
+
+/// Runs `git fetch --prune` for one repository at a time: each repository at
+/// most once per interval, and never two fetches closer than the spacing.
+pub struct FetchScheduler {
+    interval: Duration,
+    spacing: Duration,
+    /// Last attempt per repository, so a failing remote is retried no more
+    /// often than a working one.
+    attempted: HashMap<PathBuf, Instant>,
+    states: HashMap<PathBuf, FetchState>,
+    running: Option<RunningFetch>,
+    last_started: Option<Instant>,
+}
+
+impl FetchScheduler {
+    pub fn new(config: &FetchConfig) -> Self {
+        Self {
+            interval: Duration::from_secs(config.interval_secs),
+            spacing: Duration::from_secs(config.spacing_secs),
+            attempted: HashMap::new(),
+            states: HashMap::new(),
+            running: None,
+            last_started: None,
+        }
+    }
+
+    pub fn state(&self, repo: &Path) -> Option<&FetchState> {
+        self.states.get(repo)
+    }
+
+    /// Collects a finished fetch, then starts the most overdue repository
+    /// among `targets` if the spacing allows. Returns the repository whose
+    /// fetch just finished so its entries can be refreshed.
+    pub fn tick(&mut self, targets: &[FetchTarget]) -> Option<PathBuf> {
+        let finished = self.collect();
+
+        let spaced = self
+            .last_started
+            .is_none_or(|started| started.elapsed() >= self.spacing);
+        if self.running.is_none()
+            && spaced
+            && let Some(target) = self.next_due(targets)
+        {
+            self.start(target);
+        }
+
+        finished
+    }
+
+    fn collect(&mut self) -> Option<PathBuf> {
+        let outcome = match self.running.as_ref()?.outcome.try_recv() {
+            Ok(outcome) => outcome,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => Err("the fetch thread exited".to_string()),
+        };
+
+        let running = self.running.take()?;
+        match outcome {
+            Ok(()) => {
+                self.states.remove(&running.repo);
+            }
+            Err(message) => {
+                self.states.insert(
+                    running.repo.clone(),
+                    FetchState::Failed {
+                        at: unix_now(),
+                        message,
+                    },
+                );
+            }
+        }
+        Some(running.repo)
+    }
+
+    fn next_due<'a>(&self, targets: &'a [FetchTarget]) -> Option<&'a FetchTarget> {
+        let now = unix_now();
+        targets
+            .iter()
+            .filter_map(|target| {
+                // Whichever is more recent: our last attempt, or any fetch
+                // (ours or the user's) recorded in `FETCH_HEAD`.
+                let since_fetch = target
+                    .last_fetch
+                    .map(|time| Duration::from_secs((now - time).max(0) as u64));
+                let since_attempt = self.attempted.get(&target.repo).map(Instant::elapsed);
+                let waited = match (since_fetch, since_attempt) {
+                    (Some(fetch), Some(attempt)) => fetch.min(attempt),
+                    (Some(waited), None) | (None, Some(waited)) => waited,
+                    (None, None) => Duration::MAX,
+                };
+                (waited >= self.interval).then_some((target, waited))
+            })
+            .max_by_key(|(_, waited)| *waited)
+            .map(|(target, _)| target)
+    }
+
+    fn start(&mut self, target: &FetchTarget) {
+        let (tx, rx) = mpsc::channel();
+        let path = target.path.clone();
+        thread::spawn(move || {
+            let _ = tx.send(git::fetch(&path).map_err(|err| failure_message(&err)));
+        });
+
+        let now = Instant::now();
+        self.attempted.insert(target.repo.clone(), now);
+        self.last_started = Some(now);
+        self.states
+            .insert(target.repo.clone(), FetchState::Fetching);
+        self.running = Some(RunningFetch {
+            repo: target.repo.clone(),
+            outcome: rx,
+        });
+    }
+}
+
+/// One line explaining why a fetch failed.
+pub fn failure_message(err: &anyhow::Error) -> String {
+    match err.downcast_ref::<git::GitError>() {
+        Some(err) => err.explanation(),
+        None => err.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, process::Command};
+
+    use super::*;
+
+    fn scheduler(interval_secs: u64, spacing_secs: u64) -> FetchScheduler {
+        FetchScheduler::new(&FetchConfig {
+            enabled: true,
+            interval_secs,
+            spacing_secs,
+        })
+    }
+
+    fn target(repo: &str, last_fetch: Option<i64>) -> FetchTarget {
+        FetchTarget {
+            repo: PathBuf::from(repo),
+            path: PathBuf::from(repo),
+            last_fetch,
+        }
+    }
+
+    fn due<'a>(scheduler: &FetchScheduler, targets: &'a [FetchTarget]) -> Option<&'a str> {
+        scheduler
+            .next_due(targets)
+            .map(|target| target.repo.to_str().unwrap())
+    }
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .args(["-c", "user.name=gmux", "-c", "user.email=gmux@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {args:?}: {output:?}");
+        String::from_utf8(output.stdout).unwrap().trim().to_string()
+    }
+
+    #[test]
+    fn next_due_waits_for_the_interval() {
+        let now = unix_now();
+        let scheduler = scheduler(60, 0);
+
+        assert_eq!(due(&scheduler, &[target("never", None)]), Some("never"));
+        assert_eq!(due(&scheduler, &[target("recent", Some(now - 10))]), None);
+        assert_eq!(
+            due(&scheduler, &[target("old", Some(now - 120))]),
+            Some("old")
+        );
+    }
+
+    #[test]
+    fn next_due_prefers_the_most_overdue() {
+        let now = unix_now();
+        let scheduler = scheduler(60, 0);
+
+        let targets = [
+            target("old", Some(now - 120)),
+            target("older", Some(now - 600)),
+        ];
+        assert_eq!(due(&scheduler, &targets), Some("older"));
+
+        let targets = [target("older", Some(now - 600)), target("never", None)];
+        assert_eq!(due(&scheduler, &targets), Some("never"));
+    }
+
+    #[test]
+    fn next_due_counts_from_the_latest_fetch_or_attempt() {
+        let now = unix_now();
+        let mut scheduler = scheduler(60, 0);
+        let ago = |secs| {
+            Instant::now()
+                .checked_sub(Duration::from_secs(secs))
+                .unwrap()
+        };
+
+        // An attempt that failed without touching FETCH_HEAD still counts.
+        scheduler
+            .attempted
+            .insert(PathBuf::from("failing"), ago(10));
+        assert_eq!(due(&scheduler, &[target("failing", Some(now - 600))]), None);
+        assert_eq!(due(&scheduler, &[target("failing", None)]), None);
+
+        // So does a fetch the user ran since our last attempt.
+        scheduler
+            .attempted
+            .insert(PathBuf::from("fetched"), ago(600));
+        assert_eq!(due(&scheduler, &[target("fetched", Some(now - 10))]), None);
+        assert_eq!(
+            due(&scheduler, &[target("fetched", Some(now - 120))]),
+            Some("fetched")
+        );
+    }
+
+    #[test]
+    fn tick_keeps_fetches_apart() {
+        let repo = "/nonexistent/gmux-fetch-spacing";
+        let targets = [target(repo, None)];
+        let mut scheduler = scheduler(60, 3600);
+        scheduler.last_started = Some(Instant::now());
+
+        assert_eq!(scheduler.tick(&targets), None);
+        assert!(scheduler.state(Path::new(repo)).is_none());
+
+        scheduler.spacing = Duration::ZERO;
+        scheduler.tick(&targets);
+        assert!(matches!(
+            scheduler.state(Path::new(repo)),
+            Some(FetchState::Fetching)
+        ));
+    }
+
+    #[test]
+    fn tick_fetches_new_commits() {
+        let dir = std::env::temp_dir().join(format!("gmux-fetch-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let remote = dir.join("remote.git");
+        let clone = dir.join("clone");
+        let pusher = dir.join("pusher");
+
+        git(&dir, &["init", "--quiet", "--bare", "remote.git"]);
+        git(&dir, &["clone", "--quiet", "remote.git", "pusher"]);
+        git(
+            &pusher,
+            &["commit", "--quiet", "--allow-empty", "-m", "first"],
+        );
+        git(&pusher, &["push", "--quiet", "origin", "HEAD:main"]);
+        git(
+            &dir,
+            &[
+                "clone",
+                "--quiet",
+                "--branch",
+                "main",
+                "remote.git",
+                "clone",
+            ],
+        );
+        git(
+            &pusher,
+            &["commit", "--quiet", "--allow-empty", "-m", "second"],
+        );
+        git(&pusher, &["push", "--quiet", "origin", "HEAD:main"]);
+        let pushed = git(&pusher, &["rev-parse", "HEAD"]);
+
+        let targets = [FetchTarget {
+            repo: remote.clone(),
+            path: clone.clone(),
+            last_fetch: None,
+        }];
+        let mut scheduler = scheduler(60, 0);
+        let deadline = Instant::now() + Duration::from_secs(30);
+        let finished = loop {
+            if let Some(repo) = scheduler.tick(&targets) {
+                break repo;
+            }
+            assert!(Instant::now() < deadline, "fetch did not finish");
+            thread::sleep(Duration::from_millis(10));
+        };
+
+        assert_eq!(finished, remote);
+        assert!(scheduler.state(&remote).is_none());
+        assert_eq!(git(&clone, &["rev-parse", "origin/main"]), pushed);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,95 +1,2053 @@
-use std::{path::Path, process::Command};
+mod gitoxide;
 
-use anyhow::{Context, Result, anyhow};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    process::{Child, Command, Output, Stdio},
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
-#[derive(Debug, Default, Clone, Copy)]
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::GitBackendKind;
+
+pub use gitoxide::GitoxideBackend;
+
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+/// Fetches talk to a server and may transfer a lot of objects.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Seconds since the Unix epoch.
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Returned when a git invocation was killed for exceeding its time budget.
+#[derive(Debug)]
+pub struct CommandTimedOut {
+    pub command: String,
+    pub timeout: Duration,
+}
+
+impl fmt::Display for CommandTimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` did not finish within {}s",
+            self.command,
+            self.timeout.as_secs()
+        )
+    }
+}
+
+impl std::error::Error for CommandTimedOut {}
+
+/// Why git could not answer for a repository, classified from its stderr so
+/// the UI can show something shorter than the raw message and suggest a fix.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum GitError {
+    /// No `git` executable on PATH.
+    NotInstalled,
+    /// The repository belongs to another user and is not in `safe.directory`.
+    DubiousOwnership {
+        path: PathBuf,
+        stderr: String,
+    },
+    PermissionDenied {
+        stderr: String,
+    },
+    Corrupt {
+        stderr: String,
+    },
+    Unknown {
+        stderr: String,
+    },
+}
+
+/// Phrases git and gitoxide use when objects, refs or the index are damaged.
+const CORRUPTION_MARKERS: &[&str] = &[
+    "corrupt",
+    "bad object",
+    "bad signature",
+    "bad tree",
+    "invalid object",
+    "index file smaller than expected",
+    "unable to read",
+    "appears to be broken",
+    "not a valid object",
+    "could not decode",
+];
+
+impl GitError {
+    pub fn classify(path: &Path, stderr: &str) -> Self {
+        let stderr = stderr.trim().to_string();
+        let lower = stderr.to_lowercase();
+        if lower.contains("dubious ownership") || lower.contains("safe.directory") {
+            GitError::DubiousOwnership {
+                path: path.to_path_buf(),
+                stderr,
+            }
+        } else if lower.contains("permission denied") {
+            GitError::PermissionDenied { stderr }
+        } else if CORRUPTION_MARKERS
+            .iter()
+            .any(|marker| lower.contains(marker))
+        {
+            GitError::Corrupt { stderr }
+        } else {
+            GitError::Unknown { stderr }
+        }
+    }
+
+    fn from_output(path: &Path, output: &Output) -> Self {
+        Self::classify(path, &String::from_utf8_lossy(&output.stderr))
+    }
+
+    fn from_spawn(path: &Path, err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::NotFound if path.is_dir() => GitError::NotInstalled,
+            io::ErrorKind::PermissionDenied => GitError::PermissionDenied {
+                stderr: err.to_string(),
+            },
+            _ => GitError::Unknown {
+                stderr: err.to_string(),
+            },
+        }
+    }
+
+    /// Short enough to fit in a list row.
+    pub fn label(&self) -> &'static str {
+        match self {
+            GitError::NotInstalled => "git not found",
+            GitError::DubiousOwnership { .. } => "unsafe repo",
+            GitError::PermissionDenied { .. } => "permission denied",
+            GitError::Corrupt { .. } => "corrupt repo",
+            GitError::Unknown { .. } => "git error",
+        }
+    }
+
+    pub fn explanation(&self) -> String {
+        match self {
+            GitError::NotInstalled => "The git executable was not found on PATH.".to_string(),
+            GitError::DubiousOwnership { path, .. } => format!(
+                "git refuses to read {}: it is owned by another user.",
+                path.display()
+            ),
+            GitError::PermissionDenied { stderr } => {
+                format!("Permission denied: {}", summary_line(stderr))
+            }
+            GitError::Corrupt { stderr } => {
+                format!("The repository looks corrupt: {}", summary_line(stderr))
+            }
+            GitError::Unknown { stderr } if stderr.is_empty() => {
+                "git exited with an error and printed nothing.".to_string()
+            }
+            GitError::Unknown { stderr } => summary_line(stderr).to_string(),
+        }
+    }
+
+    pub fn suggestion(&self) -> Option<String> {
+        match self {
+            GitError::NotInstalled => Some(
+                "Install git, or set \"git_backend\": \"gitoxide\" in the gmux config.".to_string(),
+            ),
+            GitError::DubiousOwnership { path, .. } => Some(format!(
+                "If you trust it: git config --global --add safe.directory {}",
+                path.display()
+            )),
+            GitError::PermissionDenied { .. } => {
+                Some("Check the ownership and permissions of the repository files.".to_string())
+            }
+            GitError::Corrupt { .. } => Some(
+                "Run `git fsck` in the repository; re-clone it if objects are missing.".to_string(),
+            ),
+            GitError::Unknown { .. } => None,
+        }
+    }
+}
+
+/// The line worth showing: git's `fatal:` message if there is one, otherwise
+/// the first line.
+fn summary_line(text: &str) -> &str {
+    let line = text
+        .lines()
+        .find(|line| line.starts_with("fatal:"))
+        .or_else(|| text.lines().next())
+        .unwrap_or_default();
+    line.trim_start_matches("fatal:").trim()
+}
+
+impl fmt::Display for GitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.label(), self.explanation())
+    }
+}
+
+impl std::error::Error for GitError {}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
 pub struct DiffStat {
+    pub files: u32,
     pub additions: u32,
     pub deletions: u32,
 }
 
-pub fn current_branch(path: &Path) -> Result<String> {
-    let output = Command::new("git")
-        .args(["rev-parse", "--abbrev-ref", "HEAD"])
-        .current_dir(path)
-        .output()
-        .with_context(|| format!("failed to invoke git in {}", path.display()))?;
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct RepoStatus {
+    pub staged: DiffStat,
+    pub unstaged: DiffStat,
+    pub untracked: u32,
+    pub conflicted: u32,
+}
 
-    if !output.status.success() {
-        return Err(anyhow!("git rev-parse failed for {}", path.display()));
+impl RepoStatus {
+    /// Anything not yet committed, untracked files included.
+    pub fn is_dirty(&self) -> bool {
+        self.staged.files > 0
+            || self.unstaged.files > 0
+            || self.untracked > 0
+            || self.conflicted > 0
+    }
+
+    pub fn from_changes<'a>(changes: impl IntoIterator<Item = &'a PathChange>) -> Self {
+        let mut status = RepoStatus::default();
+        for change in changes {
+            let stat = match change.kind {
+                ChangeKind::Staged => &mut status.staged,
+                ChangeKind::Unstaged => &mut status.unstaged,
+                ChangeKind::Untracked => {
+                    status.untracked += 1;
+                    continue;
+                }
+                ChangeKind::Conflicted => {
+                    status.conflicted += 1;
+                    continue;
+                }
+            };
+            stat.files += 1;
+            stat.additions += change.lines.additions;
+            stat.deletions += change.lines.deletions;
+        }
+        status
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Staged,
+    Unstaged,
+    Untracked,
+    Conflicted,
+}
+
+/// One line of `git status`. A path that is both staged and modified again
+/// appears twice, once per kind.
+#[derive(Debug, Clone)]
+pub struct PathChange {
+    /// Relative to the worktree root.
+    pub path: PathBuf,
+    pub kind: ChangeKind,
+    pub lines: DiffStat,
+}
+
+impl PathChange {
+    /// Whether this change is visible from `subpath` (relative to the
+    /// worktree root).
+    fn is_within(&self, subpath: &Path) -> bool {
+        self.path.starts_with(subpath)
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct UpstreamInfo {
+    pub name: String,
+    pub ahead: u32,
+    pub behind: u32,
+    pub gone: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitSummary {
+    pub hash: String,
+    pub subject: String,
+    pub author: String,
+    /// Committer timestamp in seconds since the Unix epoch.
+    pub time: i64,
+}
+
+/// Committed work on HEAD since it forked from the repository's default
+/// branch.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct BaseComparison {
+    /// Default branch as shown, e.g. `main`.
+    pub name: String,
+    /// Ref the comparison was made against, e.g. `origin/main`.
+    pub reference: String,
+    /// Commits on HEAD since the merge-base.
+    pub commits: u32,
+    /// Diff between the merge-base and HEAD.
+    pub diff: DiffStat,
+}
+
+/// The branch a repository's work is measured against.
+#[derive(Debug, Clone, Serialize)]
+pub struct DefaultBranch {
+    /// As shown, e.g. `main`.
+    pub name: String,
+    /// Ref it resolves to, e.g. `origin/main`.
+    pub reference: String,
+    /// `reference` is a remote-tracking branch, so whatever it contains has
+    /// been published.
+    pub remote: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalBranch {
+    pub name: String,
+    pub upstream: Option<String>,
+    /// The configured upstream no longer exists, typically because it was
+    /// deleted after its pull request merged.
+    pub gone: bool,
+    /// Commits not on the upstream; zero without one.
+    pub ahead: u32,
+    /// Reachable from the default branch.
+    pub merged: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BranchInventory {
+    /// `None` when no default branch could be determined, in which case no
+    /// branch is reported as merged.
+    pub default_branch: Option<DefaultBranch>,
+    pub branches: Vec<LocalBranch>,
+}
+
+impl BaseComparison {
+    /// False on the default branch itself (or a branch tracking it) and when
+    /// HEAD has no commits of its own.
+    fn is_own_work(&self, snapshot: &RepoSnapshot) -> bool {
+        self.commits > 0
+            && self.name != snapshot.branch
+            && snapshot
+                .upstream
+                .as_ref()
+                .is_none_or(|upstream| upstream.name != self.reference)
+    }
+}
+
+/// What the name shown for HEAD refers to.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HeadKind {
+    #[default]
+    Branch,
+    /// Detached at a commit a tag points to.
+    Tag,
+    /// Detached at a commit a remote-tracking branch points to.
+    RemoteBranch,
+    /// Detached elsewhere; named by `git describe --tags`.
+    Describe,
+    /// Detached with nothing better to call it than the abbreviated hash.
+    Detached,
+}
+
+/// Submodules registered in `.gitmodules`, with how many need attention.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct SubmoduleSummary {
+    pub total: u32,
+    /// Not cloned or not checked out (`git submodule update --init` pending).
+    pub uninitialized: u32,
+    /// Checked out at a different commit than the superproject records.
+    pub out_of_date: u32,
+    /// Has modified or untracked files of its own.
+    pub dirty: u32,
+}
+
+impl SubmoduleSummary {
+    pub fn needs_attention(&self) -> bool {
+        self.uninitialized > 0 || self.out_of_date > 0 || self.dirty > 0
     }
 
-    let mut branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    if branch == "HEAD" {
-        let fallback = Command::new("git")
-            .args(["rev-parse", "--short", "HEAD"])
-            .current_dir(path)
-            .output()
-            .with_context(|| format!("failed to resolve HEAD for {}", path.display()))?;
-        if fallback.status.success() {
-            branch = format!(
-                "detached@{}",
-                String::from_utf8_lossy(&fallback.stdout).trim()
-            );
+    pub fn badge(&self) -> String {
+        let mut problems = Vec::new();
+        if self.uninitialized > 0 {
+            problems.push(format!("{} uninit", self.uninitialized));
+        }
+        if self.out_of_date > 0 {
+            problems.push(format!("{} moved", self.out_of_date));
+        }
+        if self.dirty > 0 {
+            problems.push(format!("{} dirty", self.dirty));
+        }
+        if problems.is_empty() {
+            format!("sub {}", self.total)
+        } else {
+            format!("sub {}: {}", self.total, problems.join(" · "))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct StashSummary {
+    pub count: u32,
+    /// Creation time of `stash@{0}`, in seconds since the Unix epoch.
+    pub newest: i64,
+    pub oldest: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StashEntry {
+    /// `n` in `stash@{n}`.
+    pub index: usize,
+    /// Branch the stash was made on; `None` when HEAD was detached.
+    pub branch: Option<String>,
+    pub message: String,
+    pub time: i64,
+}
+
+/// A local branch HEAD was recently on.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecentBranch {
+    pub name: String,
+    /// When HEAD last moved onto or away from it.
+    pub time: i64,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Worktree {
+    pub path: PathBuf,
+    pub head: Option<String>,
+    /// Checked-out branch; `None` when HEAD is detached.
+    pub branch: Option<String>,
+    /// This is the repository's main worktree rather than a linked one.
+    pub main: bool,
+    pub bare: bool,
+    pub locked: bool,
+    pub prunable: bool,
+}
+
+/// Identifies the repository a working directory belongs to, so that linked
+/// worktrees can be related to their main checkout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoIdentity {
+    /// Canonical path of the shared (common) git directory.
+    pub common_dir: PathBuf,
+    /// Canonical path of this worktree's own git directory, the same for
+    /// every path inside the worktree.
+    pub git_dir: PathBuf,
+    /// `path` is a linked worktree rather than the main checkout.
+    pub linked: bool,
+}
+
+/// Everything the list needs about a repository, gathered from a single
+/// `git status --porcelain=v2 --branch` invocation (plus the HEAD commit,
+/// which is only looked up when HEAD moves).
+#[derive(Debug, Default, Clone)]
+pub struct RepoSnapshot {
+    pub branch: String,
+    pub head_kind: HeadKind,
+    pub oid: Option<String>,
+    pub upstream: Option<UpstreamInfo>,
+    pub status: RepoStatus,
+    pub operation: Option<GitOperation>,
+    pub last_commit: Option<CommitSummary>,
+    /// `None` on the default branch itself, or when nothing was committed
+    /// since branching off it.
+    pub base: Option<BaseComparison>,
+    /// `None` when the repository has no submodules.
+    pub submodules: Option<SubmoduleSummary>,
+    pub stashes: Option<StashSummary>,
+    /// When `FETCH_HEAD` was last written, in seconds since the Unix epoch.
+    pub last_fetch: Option<i64>,
+    /// The paths behind `status`.
+    pub changes: Vec<PathChange>,
+}
+
+impl RepoSnapshot {
+    /// The snapshot as seen from `subpath` below the worktree root: only
+    /// changes inside it count. Branch-level information (upstream, base
+    /// comparison, stashes) is shared by the whole repository and kept.
+    pub fn scoped(&self, subpath: &Path) -> RepoSnapshot {
+        let changes: Vec<PathChange> = self
+            .changes
+            .iter()
+            .filter(|change| change.is_within(subpath))
+            .cloned()
+            .collect();
+        RepoSnapshot {
+            status: RepoStatus::from_changes(&changes),
+            changes,
+            ..self.clone()
         }
     }
+}
 
-    Ok(branch)
+/// Source of repository information. Implementations must be cheap to share
+/// across refresh threads.
+pub trait GitBackend: Send + Sync {
+    fn is_git_repo(&self, path: &Path) -> bool;
+    /// `default_branch` overrides detection of the branch HEAD is compared
+    /// against (`origin/HEAD`).
+    fn snapshot(&self, path: &Path, default_branch: Option<&str>) -> Result<RepoSnapshot>;
+    /// All worktrees of the repository containing `path`, main worktree first.
+    fn worktrees(&self, path: &Path) -> Result<Vec<Worktree>>;
+    /// Local branches, checked against the default branch (`default_branch`
+    /// overrides detection) and their upstreams.
+    fn local_branches(&self, path: &Path, default_branch: Option<&str>) -> Result<BranchInventory>;
+    /// Deletes a local branch unconditionally; callers decide what is safe.
+    fn delete_branch(&self, path: &Path, name: &str) -> Result<()>;
+    /// Stashes of the repository containing `path`, newest first.
+    fn stashes(&self, path: &Path) -> Vec<StashEntry>;
+    /// Local branches HEAD recently moved between, most recent first. The
+    /// current branch and branches deleted since are left out.
+    fn recent_branches(&self, path: &Path, limit: usize) -> Vec<RecentBranch>;
+    /// Unmerged paths of the worktree containing `path`, relative to its root.
+    fn conflicted_files(&self, path: &Path) -> Result<Vec<PathBuf>>;
+    /// Whether tracked files have changes a checkout would carry along or
+    /// refuse on. Untracked files do not count.
+    fn has_local_changes(&self, path: &Path) -> Result<bool>;
+    /// The first lock file present in the repository containing `path`.
+    fn find_lock(&self, path: &Path) -> Option<LockFile>;
+    /// Whether HEAD's commit is reachable from a local or remote-tracking
+    /// branch; a detached HEAD's commits are otherwise easily lost.
+    fn head_on_branch(&self, path: &Path) -> Result<bool>;
+    /// Ignored directories at or below `path`, whose contents git never
+    /// looks at. Nested ones inside them are not listed.
+    fn ignored_dirs(&self, path: &Path) -> Result<Vec<PathBuf>>;
 }
 
-pub fn diff_stat(path: &Path) -> Result<DiffStat> {
-    let commands: &[&[&str]] = &[&["diff", "--shortstat", "HEAD"], &["diff", "--shortstat"]];
+pub fn backend(kind: GitBackendKind) -> Arc<dyn GitBackend> {
+    match kind {
+        GitBackendKind::Cli => Arc::new(CliBackend::default()),
+        GitBackendKind::Gitoxide => Arc::new(GitoxideBackend),
+    }
+}
+
+/// Backend that shells out to the `git` executable on PATH.
+#[derive(Default)]
+pub struct CliBackend {
+    /// Commits are immutable, so summaries are memoized by object id.
+    commits: Mutex<HashMap<String, CommitSummary>>,
+    /// Merge-base comparisons keyed by (HEAD, base) object ids.
+    comparisons: Mutex<HashMap<(String, String), (u32, DiffStat)>>,
+    /// Names of detached HEADs keyed by object id and last fetch, since a
+    /// fetch moves the remote branches they may be named after. A local tag
+    /// created later goes unnoticed until restart.
+    descriptions: Mutex<HashMap<(String, i64), (HeadKind, String)>>,
+}
+
+impl GitBackend for CliBackend {
+    fn is_git_repo(&self, path: &Path) -> bool {
+        find_git_dir(path).is_some()
+    }
 
-    for args in commands {
-        let output = Command::new("git")
-            .args(*args)
-            .current_dir(path)
-            .output()
+    fn worktrees(&self, path: &Path) -> Result<Vec<Worktree>> {
+        let output = run_git(path, &["worktree", "list", "--porcelain"])
+            .with_context(|| format!("failed to invoke git worktree in {}", path.display()))?;
+
+        if !output.status.success() {
+            return Err(GitError::from_output(path, &output).into());
+        }
+
+        Ok(parse_worktree_list(&output.stdout))
+    }
+
+    fn local_branches(&self, path: &Path, default_branch: Option<&str>) -> Result<BranchInventory> {
+        let output = run_git(
+            path,
+            &[
+                "for-each-ref",
+                "--format=%(refname)%00%(upstream:short)%00%(upstream:track)",
+                "refs/heads",
+            ],
+        )
+        .with_context(|| format!("failed to invoke git for-each-ref in {}", path.display()))?;
+
+        if !output.status.success() {
+            return Err(GitError::from_output(path, &output).into());
+        }
+
+        let mut inventory = BranchInventory {
+            branches: parse_local_branches(&output.stdout),
+            ..BranchInventory::default()
+        };
+
+        let Some((default, base)) = resolve_default_branch(path, default_branch)? else {
+            return Ok(inventory);
+        };
+        let merged = format!("--merged={base}");
+        let output = run_git(
+            path,
+            &["for-each-ref", "--format=%(refname)", &merged, "refs/heads"],
+        )
+        .with_context(|| format!("failed to invoke git for-each-ref in {}", path.display()))?;
+
+        if !output.status.success() {
+            return Err(GitError::from_output(path, &output).into());
+        }
+
+        let text = String::from_utf8_lossy(&output.stdout);
+        let merged: HashSet<&str> = text
+            .lines()
+            .filter_map(|line| line.strip_prefix("refs/heads/"))
+            .collect();
+        for branch in &mut inventory.branches {
+            branch.merged = merged.contains(branch.name.as_str());
+        }
+        inventory.default_branch = Some(default);
+        Ok(inventory)
+    }
+
+    fn delete_branch(&self, path: &Path, name: &str) -> Result<()> {
+        let output = run_git(path, &["branch", "-D", "--", name])
+            .with_context(|| format!("failed to invoke git branch in {}", path.display()))?;
+
+        if !output.status.success() {
+            return Err(GitError::from_output(path, &output).into());
+        }
+        Ok(())
+    }
+
+    fn snapshot(&self, path: &Path, default_branch: Option<&str>) -> Result<RepoSnapshot> {
+        let mut snapshot = snapshot(path)?;
+        if snapshot.head_kind == HeadKind::Detached
+            && let Some(oid) = snapshot.oid.clone()
+        {
+            (snapshot.head_kind, snapshot.branch) =
+                self.describe_detached(path, &oid, &snapshot)?;
+        }
+        if let Some(oid) = snapshot.oid.clone() {
+            snapshot.last_commit = self.commit_summary(path, &oid)?;
+            snapshot.base = self
+                .base_comparison(path, &oid, default_branch)?
+                .filter(|base| base.is_own_work(&snapshot));
+        }
+        Ok(snapshot)
+    }
+
+    fn stashes(&self, path: &Path) -> Vec<StashEntry> {
+        find_git_dir(path)
+            .map(|git_dir| read_stashes(&git_dir))
+            .unwrap_or_default()
+    }
+
+    /// Read from the `checkout: moving from A to B` entries of the
+    /// worktree's HEAD reflog.
+    fn recent_branches(&self, path: &Path, limit: usize) -> Vec<RecentBranch> {
+        let Some(git_dir) = find_git_dir(path) else {
+            return Vec::new();
+        };
+        let Ok(reflog) = fs::read_to_string(git_dir.join("logs/HEAD")) else {
+            return Vec::new();
+        };
+        let current = fs::read_to_string(git_dir.join("HEAD")).ok();
+        let current = current
+            .as_deref()
+            .and_then(|head| head.trim().strip_prefix("ref: refs/heads/"));
+        let common = common_dir(&git_dir);
+        let packed = fs::read_to_string(common.join("packed-refs")).unwrap_or_default();
+        let exists = |name: &str| {
+            common.join("refs/heads").join(name).is_file()
+                || packed.lines().any(|line| {
+                    line.split_once(' ').is_some_and(|(_, reference)| {
+                        reference.strip_prefix("refs/heads/") == Some(name)
+                    })
+                })
+        };
+
+        let checkouts = reflog.lines().rev().filter_map(parse_reflog_line);
+        recent_checkouts(checkouts, current, exists, limit)
+    }
+
+    fn conflicted_files(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        let output = run_git(path, &["diff", "--name-only", "--diff-filter=U", "-z"])
             .with_context(|| format!("failed to invoke git diff in {}", path.display()))?;
 
-        if output.status.success() {
-            return Ok(parse_shortstat(&output.stdout));
+        if !output.status.success() {
+            return Err(GitError::from_output(path, &output).into());
         }
+
+        let mut files: Vec<PathBuf> = output
+            .stdout
+            .split(|byte| *byte == 0)
+            .filter(|name| !name.is_empty())
+            .map(|name| PathBuf::from(String::from_utf8_lossy(name).into_owned()))
+            .collect();
+        files.dedup();
+        Ok(files)
     }
 
-    Err(anyhow!("git diff failed for {}", path.display()))
+    fn has_local_changes(&self, path: &Path) -> Result<bool> {
+        let output = run_git(path, &["status", "--porcelain", "--untracked-files=no"])
+            .with_context(|| format!("failed to invoke git status in {}", path.display()))?;
+
+        if !output.status.success() {
+            return Err(GitError::from_output(path, &output).into());
+        }
+        Ok(!output.stdout.is_empty())
+    }
+
+    fn find_lock(&self, path: &Path) -> Option<LockFile> {
+        let git_dir = find_git_dir(path)?;
+        find_lock_in(&git_dir, worktree_root(path).unwrap_or(path))
+    }
+
+    fn head_on_branch(&self, path: &Path) -> Result<bool> {
+        let output = run_git(
+            path,
+            &[
+                "for-each-ref",
+                "--count=1",
+                "--contains=HEAD",
+                "--format=%(refname)",
+                "refs/heads",
+                "refs/remotes",
+            ],
+        )
+        .with_context(|| format!("failed to invoke git for-each-ref in {}", path.display()))?;
+
+        if !output.status.success() {
+            return Err(GitError::from_output(path, &output).into());
+        }
+        Ok(!output.stdout.is_empty())
+    }
+
+    fn ignored_dirs(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        let output = run_git(
+            path,
+            &[
+                "ls-files",
+                "--others",
+                "--ignored",
+                "--exclude-standard",
+                "--directory",
+                "-z",
+            ],
+        )
+        .with_context(|| format!("failed to invoke git ls-files in {}", path.display()))?;
+
+        if !output.status.success() {
+            return Err(GitError::from_output(path, &output).into());
+        }
+
+        // Paths are relative to `path`; inside an ignored directory the only
+        // one listed is `./`.
+        Ok(output
+            .stdout
+            .split(|byte| *byte == 0)
+            .filter_map(|name| name.strip_suffix(b"/"))
+            .map(|name| match name {
+                b"." => path.to_path_buf(),
+                name => path.join(String::from_utf8_lossy(name).as_ref()),
+            })
+            .collect())
+    }
 }
 
-fn parse_shortstat(stdout: &[u8]) -> DiffStat {
-    let mut stat = DiffStat::default();
+impl CliBackend {
+    /// Memoized [`describe_detached`], keeping the name status gave HEAD
+    /// when there is nothing better.
+    fn describe_detached(
+        &self,
+        path: &Path,
+        oid: &str,
+        snapshot: &RepoSnapshot,
+    ) -> Result<(HeadKind, String)> {
+        let key = (oid.to_string(), snapshot.last_fetch.unwrap_or_default());
+        if let Some(described) = self
+            .descriptions
+            .lock()
+            .ok()
+            .and_then(|d| d.get(&key).cloned())
+        {
+            return Ok(described);
+        }
+
+        let described = describe_detached(path, oid)?
+            .unwrap_or_else(|| (snapshot.head_kind, snapshot.branch.clone()));
+        if let Ok(mut descriptions) = self.descriptions.lock() {
+            descriptions.insert(key, described.clone());
+        }
+        Ok(described)
+    }
+
+    fn commit_summary(&self, path: &Path, oid: &str) -> Result<Option<CommitSummary>> {
+        if let Some(summary) = self.commits.lock().ok().and_then(|c| c.get(oid).cloned()) {
+            return Ok(Some(summary));
+        }
+
+        let output = run_git(path, &["log", "-1", "--format=%h%x00%s%x00%an%x00%ct", oid])
+            .with_context(|| format!("failed to invoke git log in {}", path.display()))?;
+
+        if !output.status.success() {
+            return Ok(None);
+        }
+
+        let summary = parse_commit_summary(&output.stdout);
+        if let (Some(summary), Ok(mut commits)) = (&summary, self.commits.lock()) {
+            commits.insert(oid.to_string(), summary.clone());
+        }
+        Ok(summary)
+    }
+
+    fn base_comparison(
+        &self,
+        path: &Path,
+        head: &str,
+        default_branch: Option<&str>,
+    ) -> Result<Option<BaseComparison>> {
+        let Some((default, base)) = resolve_default_branch(path, default_branch)? else {
+            return Ok(None);
+        };
+
+        let key = (head.to_string(), base.clone());
+        let cached = self
+            .comparisons
+            .lock()
+            .ok()
+            .and_then(|c| c.get(&key).copied());
+        let (commits, diff) = match cached {
+            Some(comparison) => comparison,
+            None => {
+                let Some(comparison) = compare_with_base(path, head, &base)? else {
+                    return Ok(None);
+                };
+                if let Ok(mut comparisons) = self.comparisons.lock() {
+                    comparisons.insert(key, comparison);
+                }
+                comparison
+            }
+        };
+
+        Ok(Some(BaseComparison {
+            name: default.name,
+            reference: default.reference,
+            commits,
+            diff,
+        }))
+    }
+}
+
+/// Resolves the configured default branch, or `origin/HEAD`, to the branch
+/// and the commit it points at.
+fn resolve_default_branch(
+    path: &Path,
+    configured: Option<&str>,
+) -> Result<Option<(DefaultBranch, String)>> {
+    // Branch names are read straight from the ref files rather than spawning
+    // git on every refresh; anything else (commit ids, `main~2`, reftable
+    // repositories) is left to `git rev-parse`.
+    let common = find_git_dir(path)
+        .map(|git_dir| common_dir(&git_dir))
+        .filter(|common| !common.join("reftable").exists());
+    let resolved = match (common, configured) {
+        (Some(common), None) => read_symbolic_ref(&common, "refs/remotes/origin/HEAD"),
+        (Some(common), Some(name)) => match find_branch_ref(&common, name) {
+            Some(found) => Some(found),
+            None => rev_parse_ref(path, name)?,
+        },
+        (None, reference) => rev_parse_ref(path, reference.unwrap_or("origin/HEAD"))?,
+    };
+    let Some((full, oid)) = resolved else {
+        return Ok(None);
+    };
+
+    let resolved = ["refs/heads/", "refs/remotes/", "refs/tags/"]
+        .iter()
+        .find_map(|prefix| full.strip_prefix(prefix))
+        .unwrap_or(&full);
+    // A remote-tracking branch stands for the local branch of the same name,
+    // e.g. `main` for a configured `origin/main`.
+    let remote = full.starts_with("refs/remotes/");
+    let name = match configured {
+        Some(name) if !remote => name.to_string(),
+        _ => default_branch_name(resolved),
+    };
+    Ok(Some((
+        DefaultBranch {
+            name,
+            reference: resolved.to_string(),
+            remote,
+        },
+        oid,
+    )))
+}
+
+/// Full name and commit of `reference`, as `git rev-parse` resolves it.
+fn rev_parse_ref(path: &Path, reference: &str) -> Result<Option<(String, String)>> {
+    let peeled = format!("{reference}^{{commit}}");
+    let output = run_git(
+        path,
+        &["rev-parse", &peeled, "--symbolic-full-name", reference],
+    )
+    .with_context(|| format!("failed to invoke git rev-parse in {}", path.display()))?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    let mut lines = text.lines();
+    let (Some(oid), Some(full)) = (lines.next(), lines.next()) else {
+        return Ok(None);
+    };
+    Ok(Some((full.to_string(), oid.to_string())))
+}
+
+/// Resolves a branch name with `git rev-parse`'s precedence. `None` when it
+/// is not a branch, or a tag of the same name would win.
+fn find_branch_ref(common: &Path, name: &str) -> Option<(String, String)> {
+    if [format!("refs/{name}"), format!("refs/tags/{name}")]
+        .iter()
+        .any(|full| read_ref(common, full).is_some())
+    {
+        return None;
+    }
+    ["refs/heads", "refs/remotes"]
+        .iter()
+        .map(|prefix| format!("{prefix}/{name}"))
+        .find_map(|full| read_ref(common, &full).map(|oid| (full, oid)))
+}
+
+/// Target and object id of a symbolic ref such as `origin/HEAD`. Symbolic
+/// refs are never packed.
+fn read_symbolic_ref(common: &Path, full: &str) -> Option<(String, String)> {
+    let contents = fs::read_to_string(common.join(full)).ok()?;
+    let target = contents.trim().strip_prefix("ref: ")?;
+    read_ref(common, target).map(|oid| (target.to_string(), oid))
+}
+
+/// Object id `full` points to, from its loose file or `packed-refs`.
+fn read_ref(common: &Path, full: &str) -> Option<String> {
+    if let Ok(contents) = fs::read_to_string(common.join(full)) {
+        let oid = contents.trim();
+        let direct = !oid.is_empty() && oid.bytes().all(|byte| byte.is_ascii_hexdigit());
+        return direct.then(|| oid.to_string());
+    }
+    let packed = fs::read_to_string(common.join("packed-refs")).ok()?;
+    packed.lines().find_map(|line| {
+        let (oid, name) = line.split_once(' ')?;
+        (name == full).then(|| oid.to_string())
+    })
+}
+
+fn parse_local_branches(stdout: &[u8]) -> Vec<LocalBranch> {
+    let text = String::from_utf8_lossy(stdout);
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\0');
+            let name = fields.next()?.strip_prefix("refs/heads/")?;
+            let upstream = fields.next().filter(|upstream| !upstream.is_empty());
+            // `[gone]`, or e.g. `[ahead 2, behind 1]`; empty when up to date.
+            let track = fields.next().unwrap_or_default();
+            let ahead = track
+                .trim_matches(['[', ']'])
+                .split(", ")
+                .find_map(|part| part.strip_prefix("ahead "))
+                .and_then(|count| count.parse().ok())
+                .unwrap_or(0);
+            Some(LocalBranch {
+                name: name.to_string(),
+                upstream: upstream.map(str::to_string),
+                gone: track == "[gone]",
+                ahead,
+                merged: false,
+            })
+        })
+        .collect()
+}
+
+/// `origin/main` -> `main`; the remote is implied for a detected default.
+fn default_branch_name(reference: &str) -> String {
+    reference
+        .split_once('/')
+        .map_or(reference, |(_, branch)| branch)
+        .to_string()
+}
+
+fn compare_with_base(path: &Path, head: &str, base: &str) -> Result<Option<(u32, DiffStat)>> {
+    let output = run_git(path, &["merge-base", head, base])
+        .with_context(|| format!("failed to invoke git merge-base in {}", path.display()))?;
+
+    // Unrelated histories have no merge-base.
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let merge_base = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    let range = format!("{merge_base}..{head}");
+    let output = run_git(path, &["rev-list", "--count", &range])
+        .with_context(|| format!("failed to invoke git rev-list in {}", path.display()))?;
+
+    if !output.status.success() {
+        return Err(GitError::from_output(path, &output).into());
+    }
+    let commits = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .unwrap_or(0);
+
+    let diff = if commits > 0 {
+        numstat(path, &["diff", "--numstat", &merge_base, head])?
+    } else {
+        DiffStat::default()
+    };
+    Ok(Some((commits, diff)))
+}
+
+fn parse_worktree_list(stdout: &[u8]) -> Vec<Worktree> {
     let text = String::from_utf8_lossy(stdout);
+    let mut worktrees: Vec<Worktree> = Vec::new();
 
-    for part in text.split(',') {
-        let trimmed = part.trim();
-        if trimmed.is_empty() {
+    for line in text.lines() {
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        if key == "worktree" {
+            worktrees.push(Worktree {
+                path: PathBuf::from(value),
+                main: worktrees.is_empty(),
+                ..Worktree::default()
+            });
             continue;
         }
 
-        if trimmed.contains("insertion") {
-            if let Some(value) = extract_number(trimmed) {
-                stat.additions = value;
+        let Some(current) = worktrees.last_mut() else {
+            continue;
+        };
+        match key {
+            "HEAD" => current.head = Some(value.to_string()),
+            "branch" => {
+                current.branch = Some(
+                    value
+                        .strip_prefix("refs/heads/")
+                        .unwrap_or(value)
+                        .to_string(),
+                );
             }
-        } else if trimmed.contains("deletion") {
-            if let Some(value) = extract_number(trimmed) {
-                stat.deletions = value;
+            "bare" => current.bare = true,
+            "locked" => current.locked = true,
+            "prunable" => current.prunable = true,
+            _ => {}
+        }
+    }
+
+    worktrees
+}
+
+fn parse_commit_summary(stdout: &[u8]) -> Option<CommitSummary> {
+    let text = String::from_utf8_lossy(stdout);
+    let mut fields = text.trim_end().splitn(4, '\0');
+    Some(CommitSummary {
+        hash: fields.next()?.to_string(),
+        subject: fields.next()?.to_string(),
+        author: fields.next()?.to_string(),
+        time: fields.next()?.trim().parse().ok()?,
+    })
+}
+
+fn snapshot(path: &Path) -> Result<RepoSnapshot> {
+    // Paths are reported relative to the worktree root, not to `path`.
+    // Untracked directories are listed file by file so each file counts.
+    let output = run_git(
+        path,
+        &[
+            "status",
+            "--porcelain=v2",
+            "--branch",
+            "--untracked-files=all",
+            "-z",
+        ],
+    )
+    .with_context(|| format!("failed to invoke git status in {}", path.display()))?;
+
+    if !output.status.success() {
+        return Err(GitError::from_output(path, &output).into());
+    }
+
+    let mut snapshot = parse_status_v2(&output.stdout);
+    for (kind, args) in [
+        (
+            ChangeKind::Staged,
+            &["diff", "--cached", "--numstat", "-z"][..],
+        ),
+        (ChangeKind::Unstaged, &["diff", "--numstat", "-z"][..]),
+    ] {
+        if !snapshot.changes.iter().any(|change| change.kind == kind) {
+            continue;
+        }
+        let lines = file_numstat(path, args)?;
+        for change in snapshot
+            .changes
+            .iter_mut()
+            .filter(|change| change.kind == kind)
+        {
+            if let Some(stat) = lines.get(&change.path) {
+                change.lines = *stat;
             }
         }
     }
+    snapshot.status = RepoStatus::from_changes(&snapshot.changes);
 
-    stat
+    snapshot.submodules =
+        worktree_root(path).and_then(|root| submodule_summary(root, snapshot.submodules));
+
+    if let Some(git_dir) = find_git_dir(path) {
+        snapshot.operation = in_progress_operation(&git_dir);
+        snapshot.stashes = stash_summary(&read_stashes(&git_dir));
+        snapshot.last_fetch = last_fetch(&git_dir);
+    }
+    Ok(snapshot)
 }
 
-fn extract_number(text: &str) -> Option<u32> {
-    text.split_whitespace()
-        .find(|token| token.chars().all(|ch| ch.is_ascii_digit()))
-        .and_then(|token| token.parse().ok())
+/// Names a detached HEAD after a tag or remote branch pointing at `oid`,
+/// falling back to `git describe --tags`.
+fn describe_detached(path: &Path, oid: &str) -> Result<Option<(HeadKind, String)>> {
+    let output = run_git(
+        path,
+        &[
+            "for-each-ref",
+            "--format=%(refname)",
+            "--points-at",
+            oid,
+            "refs/tags",
+            "refs/remotes",
+        ],
+    )
+    .with_context(|| format!("failed to invoke git for-each-ref in {}", path.display()))?;
+
+    if output.status.success() {
+        let text = String::from_utf8_lossy(&output.stdout);
+        let refs: Vec<&str> = text.lines().collect();
+        if let Some(tag) = refs.iter().find_map(|name| name.strip_prefix("refs/tags/")) {
+            return Ok(Some((HeadKind::Tag, tag.to_string())));
+        }
+        if let Some(remote) = refs
+            .iter()
+            .filter_map(|name| name.strip_prefix("refs/remotes/"))
+            .find(|name| !name.ends_with("/HEAD"))
+        {
+            return Ok(Some((HeadKind::RemoteBranch, remote.to_string())));
+        }
+    }
+
+    let output = run_git(path, &["describe", "--tags", oid])
+        .with_context(|| format!("failed to invoke git describe in {}", path.display()))?;
+    let description = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if output.status.success() && !description.is_empty() {
+        return Ok(Some((HeadKind::Describe, description)));
+    }
+
+    Ok(None)
+}
+
+/// Submodules declared in `.gitmodules`, completing what status reported
+/// about them: status is silent about submodules that are not checked out.
+fn submodule_summary(root: &Path, status: Option<SubmoduleSummary>) -> Option<SubmoduleSummary> {
+    let gitmodules = fs::read_to_string(root.join(".gitmodules")).ok()?;
+    let mut summary = status.unwrap_or_default();
+    for path in gitmodules_paths(&gitmodules) {
+        summary.total += 1;
+        if !root.join(path).join(".git").exists() {
+            summary.uninitialized += 1;
+        }
+    }
+    (summary.total > 0).then_some(summary)
+}
+
+/// The `path = ...` values of a `.gitmodules` file.
+fn gitmodules_paths(contents: &str) -> Vec<&str> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            (key.trim() == "path").then(|| value.trim().trim_matches('"'))
+        })
+        .collect()
+}
+
+/// Line counts per path from `git diff --numstat -z`.
+fn file_numstat(path: &Path, args: &[&str]) -> Result<HashMap<PathBuf, DiffStat>> {
+    let output = run_git(path, args)
+        .with_context(|| format!("failed to invoke git diff in {}", path.display()))?;
+
+    if !output.status.success() {
+        return Err(GitError::from_output(path, &output).into());
+    }
+
+    Ok(parse_numstat_z(&output.stdout))
 }
 
-pub fn is_git_repo(path: &Path) -> bool {
-    Command::new("git")
-        .args(["rev-parse", "--show-toplevel"])
+fn numstat(path: &Path, args: &[&str]) -> Result<DiffStat> {
+    let output = run_git(path, args)
+        .with_context(|| format!("failed to invoke git diff in {}", path.display()))?;
+
+    if !output.status.success() {
+        return Err(GitError::from_output(path, &output).into());
+    }
+
+    Ok(parse_numstat(&output.stdout))
+}
+
+/// Runs git in `path`, killing it if it has not finished within
+/// [`COMMAND_TIMEOUT`] (e.g. on a stale network mount).
+fn run_git(path: &Path, args: &[&str]) -> Result<Output> {
+    // Keeps `git status` from taking `index.lock` to refresh the index,
+    // which would race the user's own commands and wake the watcher.
+    let child = Command::new("git")
+        .args(args)
+        .env("GIT_OPTIONAL_LOCKS", "0")
         .current_dir(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| GitError::from_spawn(path, err))?;
+
+    wait_with_timeout(child, || format!("git {}", args.join(" ")))
+}
+
+/// Runs `git fetch --prune` in `path`. Credential prompts are disabled and
+/// git runs in its own process group, so a remote that wants a password
+/// fails instead of stealing the terminal.
+pub fn fetch(path: &Path) -> Result<()> {
+    let mut command = Command::new("git");
+    command
+        .args(["fetch", "--prune", "--quiet"])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .current_dir(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    let child = command
+        .spawn()
+        .map_err(|err| GitError::from_spawn(path, err))?;
+
+    let output = wait_with_deadline(child, FETCH_TIMEOUT, || "git fetch --prune".to_string())?;
+    if !output.status.success() {
+        return Err(GitError::from_output(path, &output).into());
+    }
+    Ok(())
+}
+
+/// Collects the output of a child spawned with piped stdout and stderr,
+/// killing it once [`COMMAND_TIMEOUT`] has passed.
+pub fn wait_with_timeout(child: Child, command: impl FnOnce() -> String) -> Result<Output> {
+    wait_with_deadline(child, COMMAND_TIMEOUT, command)
+}
+
+fn wait_with_deadline(
+    mut child: Child,
+    timeout: Duration,
+    command: impl FnOnce() -> String,
+) -> Result<Output> {
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let mut backoff = Duration::from_millis(1);
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            // A process stuck in uninterruptible IO (e.g. on a dead network
+            // mount) outlives SIGKILL; reap it off the caller's thread.
+            thread::spawn(move || child.wait());
+            return Err(CommandTimedOut {
+                command: command(),
+                timeout,
+            }
+            .into());
+        }
+        thread::sleep(backoff);
+        backoff = (backoff * 2).min(Duration::from_millis(20));
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+fn parse_status_v2(stdout: &[u8]) -> RepoSnapshot {
+    let mut snapshot = RepoSnapshot::default();
+    let mut head: Option<String> = None;
+    let mut upstream: Option<String> = None;
+    let mut ahead_behind: Option<(u32, u32)> = None;
+    let mut records = stdout.split(|byte| *byte == 0);
+
+    while let Some(record) = records.next() {
+        let line = String::from_utf8_lossy(record);
+        let mut fields = line.split(' ');
+        match fields.next() {
+            Some("#") => match (fields.next(), fields.next()) {
+                (Some("branch.oid"), Some(oid)) if oid != "(initial)" => {
+                    snapshot.oid = Some(oid.to_string());
+                }
+                (Some("branch.head"), Some(name)) => head = Some(name.to_string()),
+                (Some("branch.upstream"), Some(name)) => upstream = Some(name.to_string()),
+                (Some("branch.ab"), Some(ahead)) => {
+                    let behind = fields.next().unwrap_or_default();
+                    ahead_behind = Some((
+                        ahead.trim_start_matches('+').parse().unwrap_or(0),
+                        behind.trim_start_matches('-').parse().unwrap_or(0),
+                    ));
+                }
+                _ => {}
+            },
+            Some(kind @ ("1" | "2")) => {
+                let xy = fields.next().unwrap_or_default().as_bytes();
+                // `S<c><m><u>`: a submodule whose checked out commit moved, or
+                // with modified or untracked content of its own.
+                let sub = fields.next().unwrap_or_default().as_bytes();
+                if sub.first() == Some(&b'S') {
+                    let submodules = snapshot.submodules.get_or_insert_default();
+                    if sub.get(1) == Some(&b'C') {
+                        submodules.out_of_date += 1;
+                    }
+                    if sub.get(2) == Some(&b'M') || sub.get(3) == Some(&b'U') {
+                        submodules.dirty += 1;
+                    }
+                }
+                let path = record_path(&line, if kind == "1" { 9 } else { 10 });
+                for (flag, kind) in [
+                    (xy.first(), ChangeKind::Staged),
+                    (xy.get(1), ChangeKind::Unstaged),
+                ] {
+                    if flag.is_some_and(|flag| *flag != b'.') {
+                        snapshot.changes.push(PathChange {
+                            path: path.clone(),
+                            kind,
+                            lines: DiffStat::default(),
+                        });
+                    }
+                }
+                // Renames and copies carry the original path as a separate record.
+                if kind == "2" {
+                    records.next();
+                }
+            }
+            Some("u") => snapshot.changes.push(PathChange {
+                path: record_path(&line, 11),
+                kind: ChangeKind::Conflicted,
+                lines: DiffStat::default(),
+            }),
+            Some("?") => snapshot.changes.push(PathChange {
+                path: record_path(&line, 2),
+                kind: ChangeKind::Untracked,
+                lines: DiffStat::default(),
+            }),
+            _ => {}
+        }
+    }
+    snapshot.status = RepoStatus::from_changes(&snapshot.changes);
+
+    snapshot.branch = match head.as_deref() {
+        Some("(detached)") | None => {
+            snapshot.head_kind = HeadKind::Detached;
+            match &snapshot.oid {
+                Some(oid) => format!("detached@{}", &oid[..oid.len().min(7)]),
+                None => "HEAD".to_string(),
+            }
+        }
+        Some(name) => name.to_string(),
+    };
+
+    // `branch.ab` is omitted when the configured upstream no longer exists.
+    snapshot.upstream = upstream.map(|name| {
+        let (ahead, behind) = ahead_behind.unwrap_or_default();
+        UpstreamInfo {
+            name,
+            ahead,
+            behind,
+            gone: ahead_behind.is_none(),
+        }
+    });
+
+    snapshot
+}
+
+/// The path ending a porcelain v2 record of `fields` space-separated fields;
+/// the path itself may contain spaces.
+fn record_path(line: &str, fields: usize) -> PathBuf {
+    PathBuf::from(line.splitn(fields, ' ').nth(fields - 1).unwrap_or_default())
+}
+
+fn parse_numstat(stdout: &[u8]) -> DiffStat {
+    let mut stat = DiffStat::default();
+    let text = String::from_utf8_lossy(stdout);
+
+    for line in text.lines() {
+        let mut fields = line.split('\t');
+        let additions = fields.next().and_then(|value| value.parse::<u32>().ok());
+        let deletions = fields.next().and_then(|value| value.parse::<u32>().ok());
+        stat.files += 1;
+        stat.additions += additions.unwrap_or(0);
+        stat.deletions += deletions.unwrap_or(0);
+    }
+
+    stat
+}
+
+/// Parses `--numstat -z` output, where renames are written as an empty path
+/// followed by the old and new paths as separate records.
+fn parse_numstat_z(stdout: &[u8]) -> HashMap<PathBuf, DiffStat> {
+    let mut stats = HashMap::new();
+    let mut records = stdout
+        .split(|byte| *byte == 0)
+        .map(|record| String::from_utf8_lossy(record).into_owned());
+
+    while let Some(record) = records.next() {
+        let mut fields = record.splitn(3, '\t');
+        let (Some(additions), Some(deletions), Some(path)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let path = if path.is_empty() {
+            records.next();
+            records.next().unwrap_or_default()
+        } else {
+            path.to_string()
+        };
+        stats.insert(
+            PathBuf::from(path),
+            DiffStat {
+                files: 1,
+                additions: additions.parse().unwrap_or(0),
+                deletions: deletions.parse().unwrap_or(0),
+            },
+        );
+    }
+
+    stats
+}
+
+/// Locates the git directory for `path` without spawning git, following
+/// `.git` files used by linked worktrees and submodules.
+pub fn find_git_dir(path: &Path) -> Option<PathBuf> {
+    for dir in path.ancestors() {
+        let candidate = dir.join(".git");
+        if candidate.is_dir() {
+            return Some(candidate);
+        }
+        if candidate.is_file() {
+            let contents = fs::read_to_string(&candidate).ok()?;
+            let target = contents.trim().strip_prefix("gitdir:")?.trim();
+            return Some(dir.join(target));
+        }
+    }
+    None
+}
+
+/// The top of the working tree containing `path`, i.e. the directory holding
+/// its `.git` entry.
+pub fn worktree_root(path: &Path) -> Option<&Path> {
+    path.ancestors().find(|dir| dir.join(".git").exists())
+}
+
+/// Where `path` sits below its worktree root; `None` for the root itself.
+pub fn repo_subpath(path: &Path) -> Option<PathBuf> {
+    let root = worktree_root(path)?;
+    let subpath = path.strip_prefix(root).ok()?;
+    (!subpath.as_os_str().is_empty()).then(|| subpath.to_path_buf())
+}
+
+/// The directory holding refs shared by all worktrees of a repository.
+fn common_dir(git_dir: &Path) -> PathBuf {
+    match fs::read_to_string(git_dir.join("commondir")) {
+        Ok(contents) => git_dir.join(contents.trim()),
+        Err(_) => git_dir.to_path_buf(),
+    }
+}
+
+/// Resolves which repository `path` belongs to without spawning git.
+pub fn repo_identity(path: &Path) -> Option<RepoIdentity> {
+    let git_dir = find_git_dir(path)?;
+    let common_dir = common_dir(&git_dir);
+    let common_dir = common_dir.canonicalize().unwrap_or(common_dir);
+    let git_dir = git_dir.canonicalize().unwrap_or(git_dir);
+    Some(RepoIdentity {
+        linked: git_dir != common_dir,
+        common_dir,
+        git_dir,
+    })
+}
+
+/// When the repository behind `git_dir` was last fetched. `FETCH_HEAD` is
+/// written per worktree, so the newest of this worktree's and the main
+/// checkout's is used. A failed fetch leaves it empty and is not counted.
+pub fn last_fetch(git_dir: &Path) -> Option<i64> {
+    [git_dir.to_path_buf(), common_dir(git_dir)]
+        .iter()
+        .filter_map(|dir| fs::metadata(dir.join("FETCH_HEAD")).ok())
+        .filter(|metadata| metadata.len() > 0)
+        .filter_map(|metadata| metadata.modified().ok())
+        .max()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|age| age.as_secs() as i64)
+}
+
+/// Collects the branches named by `checkout: moving from A to B` reflog
+/// messages, given newest first as `(time, message)`.
+fn recent_checkouts<'a>(
+    reflog: impl Iterator<Item = (i64, &'a str)>,
+    current: Option<&str>,
+    exists: impl Fn(&str) -> bool,
+    limit: usize,
+) -> Vec<RecentBranch> {
+    let mut recent: Vec<RecentBranch> = Vec::new();
+    for (time, message) in reflog {
+        let Some((from, to)) = message
+            .strip_prefix("checkout: moving from ")
+            .and_then(|moves| moves.rsplit_once(" to "))
+        else {
+            continue;
+        };
+        for name in [to, from] {
+            if Some(name) != current
+                && !recent.iter().any(|branch| branch.name == name)
+                && exists(name)
+            {
+                recent.push(RecentBranch {
+                    name: name.to_string(),
+                    time,
+                });
+            }
+        }
+        if recent.len() >= limit {
+            break;
+        }
+    }
+    recent.truncate(limit);
+    recent
+}
+
+pub fn stash_push(path: &Path, message: &str) -> Result<()> {
+    let output = run_git(path, &["stash", "push", "--message", message])
+        .with_context(|| format!("failed to invoke git stash in {}", path.display()))?;
+
+    if !output.status.success() {
+        return Err(GitError::from_output(path, &output).into());
+    }
+    Ok(())
+}
+
+pub fn switch_branch(path: &Path, branch: &str) -> Result<()> {
+    let output = run_git(path, &["switch", branch])
+        .with_context(|| format!("failed to invoke git switch in {}", path.display()))?;
+
+    if !output.status.success() {
+        return Err(GitError::from_output(path, &output).into());
+    }
+    Ok(())
+}
+
+/// Reads the stash reflog directly; `git stash list` is a view over it.
+pub fn read_stashes(git_dir: &Path) -> Vec<StashEntry> {
+    let Ok(contents) = fs::read_to_string(common_dir(git_dir).join("logs/refs/stash")) else {
+        return Vec::new();
+    };
+
+    let mut stashes: Vec<StashEntry> = contents
+        .lines()
+        .rev()
+        .filter_map(parse_stash_line)
+        .collect();
+    for (index, stash) in stashes.iter_mut().enumerate() {
+        stash.index = index;
+    }
+    stashes
+}
+
+pub fn stash_summary(stashes: &[StashEntry]) -> Option<StashSummary> {
+    Some(StashSummary {
+        count: stashes.len() as u32,
+        newest: stashes.first()?.time,
+        oldest: stashes.last()?.time,
+    })
+}
+
+/// Splits a reflog line into its timestamp and message.
+fn parse_reflog_line(line: &str) -> Option<(i64, &str)> {
+    let (header, message) = line.split_once('\t')?;
+    let mut fields = header.rsplitn(3, ' ');
+    let _timezone = fields.next()?;
+    let time = fields.next()?.parse().ok()?;
+    Some((time, message))
+}
+
+/// Parses `<old> <new> <name> <<email>> <time> <tz>\t<message>`, where the
+/// message is `WIP on <branch>: <commit>` or `On <branch>: <message>`.
+fn parse_stash_line(line: &str) -> Option<StashEntry> {
+    let (time, message) = parse_reflog_line(line)?;
+    Some(stash_entry(time, message))
+}
+
+/// Splits the branch off a stash message.
+fn stash_entry(time: i64, message: &str) -> StashEntry {
+    let described = message
+        .strip_prefix("WIP on ")
+        .or_else(|| message.strip_prefix("On "));
+    let (branch, message) = match described.and_then(|rest| rest.split_once(": ")) {
+        Some((branch, rest)) => {
+            let branch = (branch != "(no branch)").then(|| branch.to_string());
+            (branch, rest.to_string())
+        }
+        None => (None, message.to_string()),
+    };
+
+    StashEntry {
+        index: 0,
+        branch,
+        message,
+        time,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OperationKind {
+    Rebase,
+    Am,
+    Merge,
+    CherryPick,
+    Revert,
+    Bisect,
+}
+
+impl OperationKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            OperationKind::Rebase => "REBASING",
+            OperationKind::Am => "AM",
+            OperationKind::Merge => "MERGING",
+            OperationKind::CherryPick => "CHERRY-PICKING",
+            OperationKind::Revert => "REVERTING",
+            OperationKind::Bisect => "BISECTING",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitOperation {
+    pub kind: OperationKind,
+    pub branch: Option<String>,
+    pub step: Option<u32>,
+    pub total: Option<u32>,
+}
+
+impl GitOperation {
+    fn new(kind: OperationKind) -> Self {
+        Self {
+            kind,
+            branch: None,
+            step: None,
+            total: None,
+        }
+    }
+
+    pub fn badge(&self) -> String {
+        match (self.step, self.total) {
+            (Some(step), Some(total)) => format!("{} {step}/{total}", self.kind.label()),
+            _ => self.kind.label().to_string(),
+        }
+    }
+}
+
+pub fn in_progress_operation(git_dir: &Path) -> Option<GitOperation> {
+    let rebase_merge = git_dir.join("rebase-merge");
+    if rebase_merge.is_dir() {
+        let mut op = GitOperation::new(OperationKind::Rebase);
+        op.branch = read_head_name(&rebase_merge.join("head-name"));
+        op.step = read_number(&rebase_merge.join("msgnum"));
+        op.total = read_number(&rebase_merge.join("end"));
+        return Some(op);
+    }
+
+    let rebase_apply = git_dir.join("rebase-apply");
+    if rebase_apply.is_dir() {
+        let kind = if rebase_apply.join("applying").exists() {
+            OperationKind::Am
+        } else {
+            OperationKind::Rebase
+        };
+        let mut op = GitOperation::new(kind);
+        op.branch = read_head_name(&rebase_apply.join("head-name"));
+        op.step = read_number(&rebase_apply.join("next"));
+        op.total = read_number(&rebase_apply.join("last"));
+        return Some(op);
+    }
+
+    if git_dir.join("MERGE_HEAD").exists() {
+        return Some(GitOperation::new(OperationKind::Merge));
+    }
+    if git_dir.join("CHERRY_PICK_HEAD").exists() {
+        return Some(GitOperation::new(OperationKind::CherryPick));
+    }
+    if git_dir.join("REVERT_HEAD").exists() {
+        return Some(GitOperation::new(OperationKind::Revert));
+    }
+    if git_dir.join("BISECT_LOG").exists() {
+        let mut op = GitOperation::new(OperationKind::Bisect);
+        op.branch = read_head_name(&git_dir.join("BISECT_START"));
+        return Some(op);
+    }
+
+    None
+}
+
+fn read_head_name(path: &Path) -> Option<String> {
+    let contents = fs::read_to_string(path).ok()?;
+    let name = contents.trim();
+    if name.is_empty() || name == "detached HEAD" {
+        return None;
+    }
+    Some(name.strip_prefix("refs/heads/").unwrap_or(name).to_string())
+}
+
+fn read_number(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Lock files git creates next to what it is about to rewrite. One left
+/// behind by a crashed process makes every later write fail.
+const LOCK_FILES: &[&str] = &["index.lock", "HEAD.lock"];
+const SHARED_LOCK_FILES: &[&str] = &["config.lock", "packed-refs.lock"];
+/// Younger locks may belong to a git process that is just starting up.
+const STALE_LOCK_AGE: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockFile {
+    pub path: PathBuf,
+    /// When the lock was created, in seconds since the Unix epoch.
+    pub since: i64,
+    /// Processes that may still hold the lock.
+    pub holders: Vec<u32>,
+}
+
+impl LockFile {
+    /// Old enough that it should have been released by now.
+    pub fn is_lingering(&self) -> bool {
+        unix_now() - self.since >= STALE_LOCK_AGE.as_secs() as i64
+    }
+
+    /// Lingering, and no process holds it.
+    pub fn is_stale(&self) -> bool {
+        self.holders.is_empty() && self.is_lingering()
+    }
+
+    /// File name of the lock, e.g. `index.lock`.
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+/// The first lock file present in `git_dir`, or in the directory it shares
+/// with other worktrees. `root` is the top of the working tree.
+fn find_lock_in(git_dir: &Path, root: &Path) -> Option<LockFile> {
+    let common = common_dir(git_dir);
+    let lock = LOCK_FILES
+        .iter()
+        .map(|name| git_dir.join(name))
+        .chain(SHARED_LOCK_FILES.iter().map(|name| common.join(name)))
+        .find(|lock| lock.is_file())?;
+
+    let since = fs::metadata(&lock)
+        .and_then(|metadata| metadata.modified())
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_secs() as i64;
+    let repo = [root, &common];
+    Some(LockFile {
+        holders: lock_holders(&lock, &repo),
+        path: lock,
+        since,
+    })
+}
+
+/// Whether `relative`, a path inside a git dir, is a lock file
+/// [`GitBackend::find_lock`] reports.
+pub fn is_reported_lock(relative: &Path) -> bool {
+    LOCK_FILES
+        .iter()
+        .chain(SHARED_LOCK_FILES)
+        .any(|name| relative == Path::new(name))
+}
+
+/// Processes that have `lock` open, or git processes running inside one of
+/// `repo`'s directories: git does not keep every lock file open while it
+/// works. Where `/proc` is unavailable neither can be told, so every running
+/// git process counts.
+fn lock_holders(lock: &Path, repo: &[&Path]) -> Vec<u32> {
+    let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let lock = canonical(lock);
+    let repo: Vec<PathBuf> = repo.iter().map(|dir| canonical(dir)).collect();
+    let Ok(processes) = fs::read_dir("/proc") else {
+        return git_processes();
+    };
+
+    processes
+        .flatten()
+        .filter_map(|process| {
+            let pid = process.file_name().to_str()?.parse().ok()?;
+            let dir = process.path();
+            let opened = fs::read_dir(dir.join("fd")).is_ok_and(|mut fds| {
+                fds.any(|fd| {
+                    fd.is_ok_and(|fd| fs::read_link(fd.path()).is_ok_and(|target| target == lock))
+                })
+            });
+            let git_inside = fs::read_to_string(dir.join("comm"))
+                .is_ok_and(|comm| comm.trim() == "git")
+                && fs::read_link(dir.join("cwd"))
+                    .is_ok_and(|cwd| repo.iter().any(|dir| cwd.starts_with(dir)));
+            (opened || git_inside).then_some(pid)
+        })
+        .collect()
+}
+
+fn git_processes() -> Vec<u32> {
+    let Ok(output) = Command::new("ps")
+        .args(["-A", "-o", "pid=", "-o", "comm="])
         .output()
-        .map(|output| output.status.success())
-        .unwrap_or(false)
+    else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (pid, command) = line.trim().split_once(' ')?;
+            let name = Path::new(command.trim()).file_name()?;
+            (name == "git").then(|| pid.parse().ok())?
+        })
+        .collect()
+}
+
+/// Removes the lock found in `path`'s repository after checking again, right
+/// before deleting it, that it is still stale.
+pub fn remove_stale_lock(backend: &dyn GitBackend, path: &Path) -> Result<()> {
+    let lock = backend.find_lock(path).context("no lock file to remove")?;
+    if !lock.is_stale() {
+        anyhow::bail!("{} is in use or too recent to remove", lock.path.display());
+    }
+    fs::remove_file(&lock.path).with_context(|| format!("failed to remove {}", lock.path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Joins porcelain records with the NUL terminators `-z` output uses.
+    fn z(records: &[&str]) -> Vec<u8> {
+        records
+            .iter()
+            .flat_map(|record| record.bytes().chain([0]))
+            .collect()
+    }
+
+    fn changes(snapshot: &RepoSnapshot, kind: ChangeKind) -> Vec<&str> {
+        snapshot
+            .changes
+            .iter()
+            .filter(|change| change.kind == kind)
+            .map(|change| change.path.to_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn status_v2_records() {
+        let snapshot = parse_status_v2(&z(&[
+            "# branch.oid 1234567890abcdef1234567890abcdef12345678",
+            "# branch.head main",
+            "1 .M N... 100644 100644 100644 aaaaaaa aaaaaaa src/with space.rs",
+            "1 MM N... 100644 100644 100644 aaaaaaa bbbbbbb both.rs",
+            "1 .M S.M. 160000 160000 160000 aaaaaaa aaaaaaa vendor/lib",
+            "2 R. N... 100644 100644 100644 aaaaaaa aaaaaaa R100 new name.rs",
+            "old name.rs",
+            "u UU N... 100644 100644 100644 100644 aaaaaaa bbbbbbb ccccccc conflict.rs",
+            "? notes/todo.txt",
+        ]));
+
+        assert_eq!(snapshot.branch, "main");
+        assert_eq!(snapshot.head_kind, HeadKind::Branch);
+        assert_eq!(
+            snapshot.oid.as_deref(),
+            Some("1234567890abcdef1234567890abcdef12345678")
+        );
+        assert_eq!(
+            changes(&snapshot, ChangeKind::Staged),
+            ["both.rs", "new name.rs"]
+        );
+        assert_eq!(
+            changes(&snapshot, ChangeKind::Unstaged),
+            ["src/with space.rs", "both.rs", "vendor/lib"]
+        );
+        assert_eq!(changes(&snapshot, ChangeKind::Conflicted), ["conflict.rs"]);
+        assert_eq!(
+            changes(&snapshot, ChangeKind::Untracked),
+            ["notes/todo.txt"]
+        );
+        assert_eq!(snapshot.status.staged.files, 2);
+        assert_eq!(snapshot.status.unstaged.files, 3);
+        assert_eq!(snapshot.status.untracked, 1);
+        assert_eq!(snapshot.status.conflicted, 1);
+        assert_eq!(snapshot.submodules.map(|s| s.dirty), Some(1));
+        assert!(snapshot.upstream.is_none());
+    }
+
+    #[test]
+    fn status_v2_submodules() {
+        let snapshot = parse_status_v2(&z(&[
+            "# branch.head main",
+            "1 .M SC.. 160000 160000 160000 aaaaaaa aaaaaaa moved",
+            "1 .M S..U 160000 160000 160000 aaaaaaa aaaaaaa untracked",
+            "1 .M SCM. 160000 160000 160000 aaaaaaa aaaaaaa both",
+        ]));
+
+        let submodules = snapshot.submodules.unwrap();
+        assert_eq!(submodules.out_of_date, 2);
+        assert_eq!(submodules.dirty, 2);
+    }
+
+    #[test]
+    fn gitmodules_file() {
+        let contents = "[submodule \"lib\"]\n\tpath = vendor/lib\n\turl = ../lib\n\
+                        [submodule \"docs\"]\n\tpath=\"docs site\"\n";
+        assert_eq!(gitmodules_paths(contents), ["vendor/lib", "docs site"]);
+    }
+
+    #[test]
+    fn status_v2_upstream() {
+        let snapshot = parse_status_v2(&z(&[
+            "# branch.oid 1234567890abcdef1234567890abcdef12345678",
+            "# branch.head feature",
+            "# branch.upstream origin/feature",
+            "# branch.ab +2 -5",
+        ]));
+        let upstream = snapshot.upstream.unwrap();
+        assert_eq!(upstream.name, "origin/feature");
+        assert_eq!((upstream.ahead, upstream.behind), (2, 5));
+        assert!(!upstream.gone);
+    }
+
+    #[test]
+    fn status_v2_gone_upstream() {
+        let snapshot = parse_status_v2(&z(&[
+            "# branch.oid 1234567890abcdef1234567890abcdef12345678",
+            "# branch.head feature",
+            "# branch.upstream origin/feature",
+        ]));
+        let upstream = snapshot.upstream.unwrap();
+        assert!(upstream.gone);
+        assert_eq!((upstream.ahead, upstream.behind), (0, 0));
+    }
+
+    #[test]
+    fn status_v2_detached_head() {
+        let snapshot = parse_status_v2(&z(&[
+            "# branch.oid 1234567890abcdef1234567890abcdef12345678",
+            "# branch.head (detached)",
+        ]));
+        assert_eq!(snapshot.head_kind, HeadKind::Detached);
+        assert_eq!(snapshot.branch, "detached@1234567");
+        assert!(snapshot.upstream.is_none());
+    }
+
+    #[test]
+    fn status_v2_initial_commit() {
+        let snapshot = parse_status_v2(&z(&[
+            "# branch.oid (initial)",
+            "# branch.head main",
+            "? README.md",
+        ]));
+        assert_eq!(snapshot.oid, None);
+        assert_eq!(snapshot.branch, "main");
+        assert_eq!(snapshot.head_kind, HeadKind::Branch);
+        assert_eq!(snapshot.status.untracked, 1);
+    }
+
+    #[test]
+    fn numstat_z_renames() {
+        let stats = parse_numstat_z(&z(&[
+            "3\t1\tsrc/main.rs",
+            "2\t0\t",
+            "old name.rs",
+            "new name.rs",
+            "-\t-\tlogo.png",
+        ]));
+        assert_eq!(stats.len(), 3);
+        let stat = stats[Path::new("src/main.rs")];
+        assert_eq!((stat.additions, stat.deletions), (3, 1));
+        let stat = stats[Path::new("new name.rs")];
+        assert_eq!((stat.additions, stat.deletions), (2, 0));
+        assert!(!stats.contains_key(Path::new("old name.rs")));
+        let stat = stats[Path::new("logo.png")];
+        assert_eq!((stat.files, stat.additions, stat.deletions), (1, 0, 0));
+    }
+
+    #[test]
+    fn worktree_list() {
+        let worktrees = parse_worktree_list(
+            b"worktree /src/app\nHEAD aaaa\nbranch refs/heads/main\n\n\
+              worktree /src/app-hotfix\nHEAD bbbb\ndetached\nlocked on a usb drive\n\n\
+              worktree /src/app-old\nHEAD cccc\nbranch refs/heads/old\nprunable gitdir file points to non-existent location\n",
+        );
+        assert_eq!(worktrees.len(), 3);
+        assert!(worktrees[0].main);
+        assert_eq!(worktrees[0].branch.as_deref(), Some("main"));
+        assert_eq!(worktrees[1].path, Path::new("/src/app-hotfix"));
+        assert_eq!(worktrees[1].branch, None);
+        assert_eq!(worktrees[1].head.as_deref(), Some("bbbb"));
+        assert!(!worktrees[1].main && worktrees[1].locked);
+        assert!(worktrees[2].prunable && !worktrees[2].locked);
+    }
+
+    #[test]
+    fn local_branch_tracking() {
+        let branches = parse_local_branches(
+            b"refs/heads/main\0origin/main\0[ahead 2, behind 1]\n\
+              refs/heads/behind\0origin/behind\0[behind 3]\n\
+              refs/heads/merged\0origin/merged\0[gone]\n\
+              refs/heads/local\0\0\n",
+        );
+        let summary: Vec<_> = branches
+            .iter()
+            .map(|b| (b.name.as_str(), b.upstream.as_deref(), b.ahead, b.gone))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("main", Some("origin/main"), 2, false),
+                ("behind", Some("origin/behind"), 0, false),
+                ("merged", Some("origin/merged"), 0, true),
+                ("local", None, 0, false),
+            ]
+        );
+    }
 }
@@ -0,0 +1,680 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, anyhow};
+use gix::{
+    bstr::{BString, ByteSlice},
+    commit::describe::SelectRef,
+    diff::blob::{Algorithm, diff, intern::InternedInput, sink::Counter},
+    dir::{self, walk::EmissionMode},
+    refs::Category,
+    remote::Direction,
+    status::{self, UntrackedFiles, index_worktree::iter::Summary},
+};
+
+use super::{
+    BaseComparison, BranchInventory, ChangeKind, CommitSummary, DefaultBranch, DiffStat,
+    GitBackend, HeadKind, LocalBranch, LockFile, PathChange, RecentBranch, RepoSnapshot,
+    RepoStatus, StashEntry, SubmoduleSummary, UpstreamInfo, Worktree, default_branch_name,
+    find_lock_in, in_progress_operation, last_fetch, recent_checkouts, stash_entry, stash_summary,
+};
+
+/// In-process backend built on gitoxide; avoids spawning `git` entirely.
+pub struct GitoxideBackend;
+
+impl GitBackend for GitoxideBackend {
+    fn is_git_repo(&self, path: &Path) -> bool {
+        gix::discover(path).is_ok()
+    }
+
+    fn worktrees(&self, path: &Path) -> Result<Vec<Worktree>> {
+        let repo = gix::discover(path)
+            .with_context(|| format!("failed to open repository at {}", path.display()))?;
+        let main = repo.main_repo()?;
+
+        let mut worktrees = vec![Worktree {
+            path: main
+                .workdir()
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| main.git_dir().to_path_buf()),
+            main: true,
+            bare: main.is_bare(),
+            ..worktree_head(&main)
+        }];
+
+        for proxy in main.worktrees()? {
+            let path = proxy.base()?;
+            let locked = proxy.is_locked();
+            let prunable = !path.exists();
+            let head = proxy
+                .into_repo_with_possibly_inaccessible_worktree()
+                .map(|linked| worktree_head(&linked))
+                .unwrap_or_default();
+            worktrees.push(Worktree {
+                path,
+                locked,
+                prunable,
+                ..head
+            });
+        }
+
+        Ok(worktrees)
+    }
+
+    fn local_branches(&self, path: &Path, default_branch: Option<&str>) -> Result<BranchInventory> {
+        let repo = gix::discover(path)
+            .with_context(|| format!("failed to open repository at {}", path.display()))?;
+        let default = resolve_default_branch(&repo, default_branch)?;
+
+        let mut inventory = BranchInventory::default();
+        for reference in repo.references()?.local_branches()? {
+            let mut reference = reference.map_err(|err| anyhow!(err))?;
+            let tip = reference.peel_to_id()?.detach();
+            let tracking = repo
+                .branch_remote_tracking_ref_name(reference.name(), Direction::Fetch)
+                .transpose()?;
+            let upstream_tip = match &tracking {
+                Some(tracking) => repo
+                    .try_find_reference(tracking.as_ref())?
+                    .map(|mut upstream| upstream.peel_to_id().map(|id| id.detach()))
+                    .transpose()?,
+                None => None,
+            };
+            let gone = tracking.is_some() && upstream_tip.is_none();
+            let ahead = match upstream_tip {
+                Some(upstream) => count_exclusive(&repo, tip, upstream)?,
+                None => 0,
+            };
+            let merged = match &default {
+                Some((_, base)) => {
+                    tip == *base
+                        || repo
+                            .merge_base(tip, *base)
+                            .is_ok_and(|merge_base| merge_base == tip)
+                }
+                None => false,
+            };
+            inventory.branches.push(LocalBranch {
+                name: reference.name().shorten().to_string(),
+                upstream: tracking.map(|tracking| tracking.shorten().to_string()),
+                gone,
+                ahead,
+                merged,
+            });
+        }
+
+        inventory.default_branch = default.map(|(default, _)| default);
+        Ok(inventory)
+    }
+
+    fn delete_branch(&self, path: &Path, name: &str) -> Result<()> {
+        let repo = gix::discover(path)
+            .with_context(|| format!("failed to open repository at {}", path.display()))?;
+        // Unlike `git branch -D`, this leaves the `branch.<name>` config
+        // section behind; it is inert without the ref.
+        repo.find_reference(format!("refs/heads/{name}").as_str())?
+            .delete()?;
+        Ok(())
+    }
+
+    fn snapshot(&self, path: &Path, default_branch: Option<&str>) -> Result<RepoSnapshot> {
+        let repo = gix::discover(path)
+            .with_context(|| format!("failed to open repository at {}", path.display()))?;
+
+        let head_name = repo.head_name()?;
+        let head_id = repo.head_id().ok().map(|id| id.detach());
+
+        let (head_kind, branch) = match (&head_name, head_id) {
+            (Some(name), _) => (HeadKind::Branch, name.shorten().to_string()),
+            (None, Some(id)) => describe_detached(&repo, id)?,
+            (None, None) => (HeadKind::Detached, "HEAD".to_string()),
+        };
+
+        let upstream = match (&head_name, head_id) {
+            (Some(name), Some(id)) => upstream_info(&repo, name.as_ref(), id)?,
+            _ => None,
+        };
+
+        let changes = repo_changes(&repo)?;
+        let mut snapshot = RepoSnapshot {
+            branch,
+            head_kind,
+            oid: head_id.map(|id| id.to_string()),
+            upstream,
+            status: RepoStatus::from_changes(&changes),
+            operation: in_progress_operation(repo.git_dir()),
+            last_commit: head_id.map(|id| commit_summary(&repo, id)).transpose()?,
+            base: None,
+            submodules: submodule_summary(&repo)?,
+            stashes: stash_summary(&read_stashes(&repo)),
+            last_fetch: last_fetch(repo.git_dir()),
+            changes,
+        };
+        if let Some(id) = head_id {
+            snapshot.base = base_comparison(&repo, id, default_branch)?
+                .filter(|base| base.is_own_work(&snapshot));
+        }
+        Ok(snapshot)
+    }
+
+    fn stashes(&self, path: &Path) -> Vec<StashEntry> {
+        gix::discover(path)
+            .map(|repo| read_stashes(&repo))
+            .unwrap_or_default()
+    }
+
+    fn recent_branches(&self, path: &Path, limit: usize) -> Vec<RecentBranch> {
+        let Ok(repo) = gix::discover(path) else {
+            return Vec::new();
+        };
+        let Ok(head) = repo.head() else {
+            return Vec::new();
+        };
+        let reflog = reflog_messages(head.log_iter().rev());
+        let current = head.referent_name().map(|name| name.shorten().to_string());
+        let exists = |name: &str| {
+            repo.try_find_reference(format!("refs/heads/{name}").as_str())
+                .is_ok_and(|reference| reference.is_some())
+        };
+
+        let checkouts = reflog
+            .iter()
+            .map(|(time, message)| (*time, message.as_str()));
+        recent_checkouts(checkouts, current.as_deref(), exists, limit)
+    }
+
+    fn conflicted_files(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        let repo = gix::discover(path)
+            .with_context(|| format!("failed to open repository at {}", path.display()))?;
+        let index = repo.index_or_empty()?;
+
+        let mut files: Vec<PathBuf> = index
+            .entries()
+            .iter()
+            .filter(|entry| entry.stage() != gix::index::entry::Stage::Unconflicted)
+            .map(|entry| entry.path(&index).to_path().map(Path::to_path_buf))
+            .collect::<Result<_, _>>()?;
+        files.dedup();
+        Ok(files)
+    }
+
+    fn has_local_changes(&self, path: &Path) -> Result<bool> {
+        let repo = gix::discover(path)
+            .with_context(|| format!("failed to open repository at {}", path.display()))?;
+        Ok(repo.is_dirty()?)
+    }
+
+    fn find_lock(&self, path: &Path) -> Option<LockFile> {
+        let repo = gix::discover(path).ok()?;
+        find_lock_in(repo.git_dir(), repo.workdir().unwrap_or(path))
+    }
+
+    fn head_on_branch(&self, path: &Path) -> Result<bool> {
+        let repo = gix::discover(path)
+            .with_context(|| format!("failed to open repository at {}", path.display()))?;
+        let head = repo.head_id()?.detach();
+
+        let references = repo.references()?;
+        for reference in references
+            .local_branches()?
+            .chain(references.remote_branches()?)
+        {
+            let tip = reference
+                .map_err(|err| anyhow!(err))?
+                .peel_to_id()?
+                .detach();
+            if tip == head || repo.merge_base(head, tip).is_ok_and(|base| base == head) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn ignored_dirs(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        let repo = gix::discover(path)
+            .with_context(|| format!("failed to open repository at {}", path.display()))?;
+        let Some(workdir) = repo.workdir() else {
+            return Ok(Vec::new());
+        };
+        let workdir = workdir.to_path_buf();
+        let within = path
+            .strip_prefix(&workdir)
+            .ok()
+            .and_then(|within| within.to_str())
+            .filter(|within| !within.is_empty())
+            .map(BString::from);
+
+        let options = repo
+            .dirwalk_options()?
+            .emit_ignored(Some(EmissionMode::CollapseDirectory));
+        let mut dirs = Vec::new();
+        for item in
+            repo.dirwalk_iter(repo.index_or_empty()?, within, Default::default(), options)?
+        {
+            let entry = item?.entry;
+            if matches!(entry.status, dir::entry::Status::Ignored(_))
+                && entry.disk_kind == Some(dir::entry::Kind::Directory)
+            {
+                dirs.push(workdir.join(entry.rela_path.to_path()?));
+            }
+        }
+        Ok(dirs)
+    }
+}
+
+/// Stashes are the entries of the `refs/stash` reflog, newest first.
+fn read_stashes(repo: &gix::Repository) -> Vec<StashEntry> {
+    let Ok(Some(stash)) = repo.try_find_reference("refs/stash") else {
+        return Vec::new();
+    };
+    let mut stashes: Vec<StashEntry> = reflog_messages(stash.log_iter().rev())
+        .into_iter()
+        .map(|(time, message)| stash_entry(time, &message))
+        .collect();
+    for (index, stash) in stashes.iter_mut().enumerate() {
+        stash.index = index;
+    }
+    stashes
+}
+
+/// Timestamps and messages of a reverse reflog iterator; a missing or
+/// unreadable log yields nothing.
+fn reflog_messages<F: std::io::Read + std::io::Seek>(
+    log: std::io::Result<Option<gix::refs::file::log::iter::Reverse<'_, F>>>,
+) -> Vec<(i64, String)> {
+    let Ok(Some(log)) = log else {
+        return Vec::new();
+    };
+    log.filter_map(Result::ok)
+        .map(|line| (line.signature.time.seconds, line.message.to_string()))
+        .collect()
+}
+
+fn worktree_head(repo: &gix::Repository) -> Worktree {
+    Worktree {
+        head: repo.head_id().ok().map(|id| id.to_string()),
+        branch: repo
+            .head_name()
+            .ok()
+            .flatten()
+            .map(|name| name.shorten().to_string()),
+        ..Worktree::default()
+    }
+}
+
+/// Names a detached HEAD after a tag or remote branch pointing at `id`,
+/// falling back to a `git describe --tags` style name.
+fn describe_detached(repo: &gix::Repository, id: gix::ObjectId) -> Result<(HeadKind, String)> {
+    let references = repo.references()?;
+    let points_at_head = |reference: Result<gix::Reference<'_>, _>| {
+        let mut reference = reference.ok()?;
+        let target = reference.peel_to_id().ok()?.detach();
+        (target == id).then(|| reference.name().shorten().to_string())
+    };
+
+    let mut tags: Vec<String> = references.tags()?.filter_map(points_at_head).collect();
+    tags.sort();
+    if let Some(tag) = tags.into_iter().next() {
+        return Ok((HeadKind::Tag, tag));
+    }
+
+    let mut remotes: Vec<String> = references
+        .remote_branches()?
+        .filter_map(points_at_head)
+        .filter(|name| !name.ends_with("/HEAD"))
+        .collect();
+    remotes.sort();
+    if let Some(remote) = remotes.into_iter().next() {
+        return Ok((HeadKind::RemoteBranch, remote));
+    }
+
+    let description = repo
+        .find_commit(id)?
+        .describe()
+        .names(SelectRef::AllTags)
+        .try_format()?;
+    Ok(match description {
+        Some(description) => (HeadKind::Describe, description.to_string()),
+        None => (
+            HeadKind::Detached,
+            format!("detached@{}", id.to_hex_with_len(7)),
+        ),
+    })
+}
+
+fn commit_summary(repo: &gix::Repository, id: gix::ObjectId) -> Result<CommitSummary> {
+    let commit = repo.find_commit(id)?;
+    let message = commit.message()?;
+    let author = commit.author()?;
+    Ok(CommitSummary {
+        hash: id.to_hex_with_len(7).to_string(),
+        subject: message.summary().to_string(),
+        author: author.name.to_string(),
+        time: commit.time()?.seconds,
+    })
+}
+
+fn submodule_summary(repo: &gix::Repository) -> Result<Option<SubmoduleSummary>> {
+    let Some(submodules) = repo.submodules()? else {
+        return Ok(None);
+    };
+
+    let mut summary = SubmoduleSummary::default();
+    for submodule in submodules {
+        summary.total += 1;
+        let state = submodule.state()?;
+        if !state.repository_exists || !state.worktree_checkout {
+            summary.uninitialized += 1;
+            continue;
+        }
+
+        let ignore = submodule.ignore()?.unwrap_or_default();
+        let status = submodule.status(ignore, false)?;
+        if status.checked_out_head_id != status.index_id {
+            summary.out_of_date += 1;
+        }
+        if status.changes.is_some_and(|changes| !changes.is_empty()) {
+            summary.dirty += 1;
+        }
+    }
+
+    Ok((summary.total > 0).then_some(summary))
+}
+
+/// Resolves the configured default branch, or `origin/HEAD`, to the branch
+/// and the commit it points at.
+fn resolve_default_branch(
+    repo: &gix::Repository,
+    configured: Option<&str>,
+) -> Result<Option<(DefaultBranch, gix::ObjectId)>> {
+    let (name, mut reference) = match configured {
+        Some(name) => (name.to_string(), name.to_string()),
+        None => {
+            let Some(origin_head) = repo.try_find_reference("refs/remotes/origin/HEAD")? else {
+                return Ok(None);
+            };
+            let Some(target) = origin_head
+                .target()
+                .try_name()
+                .map(|name| name.shorten().to_string())
+            else {
+                return Ok(None);
+            };
+            (default_branch_name(&target), target)
+        }
+    };
+
+    let Ok(base) = repo.rev_parse_single(format!("{reference}^{{commit}}").as_str()) else {
+        return Ok(None);
+    };
+    let mut remote = false;
+    if let Some(found) = repo.try_find_reference(reference.as_str())? {
+        remote = found.name().category() == Some(Category::RemoteBranch);
+        reference = found.name().shorten().to_string();
+    }
+    // A remote-tracking branch stands for the local branch of the same name,
+    // e.g. `main` for a configured `origin/main`.
+    let name = if remote {
+        default_branch_name(&reference)
+    } else {
+        name
+    };
+    Ok(Some((
+        DefaultBranch {
+            name,
+            reference,
+            remote,
+        },
+        base.detach(),
+    )))
+}
+
+fn base_comparison(
+    repo: &gix::Repository,
+    head: gix::ObjectId,
+    default_branch: Option<&str>,
+) -> Result<Option<BaseComparison>> {
+    let Some((default, base)) = resolve_default_branch(repo, default_branch)? else {
+        return Ok(None);
+    };
+    // Unrelated histories have no merge-base.
+    let Ok(merge_base) = repo.merge_base(head, base) else {
+        return Ok(None);
+    };
+    let merge_base = merge_base.detach();
+
+    let commits = count_exclusive(repo, head, merge_base)?;
+    let diff = if commits > 0 {
+        tree_diff(repo, merge_base, head)?
+    } else {
+        DiffStat::default()
+    };
+    Ok(Some(BaseComparison {
+        name: default.name,
+        reference: default.reference,
+        commits,
+        diff,
+    }))
+}
+
+fn tree_diff(repo: &gix::Repository, from: gix::ObjectId, to: gix::ObjectId) -> Result<DiffStat> {
+    use gix::object::tree::diff::ChangeDetached;
+
+    let from = repo.find_commit(from)?.tree()?;
+    let to = repo.find_commit(to)?.tree()?;
+
+    let mut stat = DiffStat::default();
+    for change in repo.diff_tree_to_tree(&from, &to, None)? {
+        let mode = change.entry_mode();
+        if mode.is_tree() {
+            continue;
+        }
+        stat.files += 1;
+        if mode.is_commit() {
+            continue;
+        }
+        let (before, after) = match &change {
+            ChangeDetached::Addition { id, .. } => (None, Some(id.as_ref())),
+            ChangeDetached::Deletion { id, .. } => (Some(id.as_ref()), None),
+            ChangeDetached::Modification {
+                previous_id, id, ..
+            } => (Some(previous_id.as_ref()), Some(id.as_ref())),
+            ChangeDetached::Rewrite { source_id, id, .. } => {
+                (Some(source_id.as_ref()), Some(id.as_ref()))
+            }
+        };
+        let lines = blob_lines(repo, before, after)?;
+        stat.additions += lines.additions;
+        stat.deletions += lines.deletions;
+    }
+    Ok(stat)
+}
+
+fn upstream_info(
+    repo: &gix::Repository,
+    name: &gix::refs::FullNameRef,
+    head: gix::ObjectId,
+) -> Result<Option<UpstreamInfo>> {
+    let Some(tracking) = repo.branch_remote_tracking_ref_name(name, Direction::Fetch) else {
+        return Ok(None);
+    };
+    let tracking = tracking?;
+
+    let mut info = UpstreamInfo {
+        name: tracking.shorten().to_string(),
+        ..UpstreamInfo::default()
+    };
+
+    let Some(mut reference) = repo.try_find_reference(tracking.as_ref())? else {
+        info.gone = true;
+        return Ok(Some(info));
+    };
+    let upstream = reference.peel_to_id()?.detach();
+
+    info.ahead = count_exclusive(repo, head, upstream)?;
+    info.behind = count_exclusive(repo, upstream, head)?;
+    Ok(Some(info))
+}
+
+fn count_exclusive(
+    repo: &gix::Repository,
+    tip: gix::ObjectId,
+    hidden: gix::ObjectId,
+) -> Result<u32> {
+    let walk = repo.rev_walk([tip]).with_hidden([hidden]).all()?;
+    let mut count = 0;
+    for commit in walk {
+        commit?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn repo_changes(repo: &gix::Repository) -> Result<Vec<PathChange>> {
+    let mut changes = Vec::new();
+    let mut conflicted: HashSet<BString> = HashSet::new();
+    let mut staged = Vec::new();
+
+    let iter = repo
+        .status(gix::progress::Discard)?
+        .untracked_files(UntrackedFiles::Files)
+        .into_iter(None)?;
+
+    for item in iter {
+        match item? {
+            status::Item::IndexWorktree(item) => {
+                let Some(summary) = item.summary() else {
+                    continue;
+                };
+                let path = item.rela_path().to_path()?.to_path_buf();
+                match summary {
+                    Summary::Conflict => {
+                        conflicted.insert(item.rela_path().to_owned());
+                    }
+                    Summary::Added => changes.push(PathChange {
+                        path,
+                        kind: ChangeKind::Untracked,
+                        lines: DiffStat::default(),
+                    }),
+                    Summary::Removed | Summary::Modified | Summary::TypeChange => {
+                        let mut lines = DiffStat::default();
+                        if let status::index_worktree::Item::Modification {
+                            entry, rela_path, ..
+                        } = &item
+                            && !entry.mode.is_submodule()
+                        {
+                            lines = worktree_lines(repo, entry.id, rela_path, summary)?;
+                        }
+                        changes.push(PathChange {
+                            path,
+                            kind: ChangeKind::Unstaged,
+                            lines,
+                        });
+                    }
+                    Summary::Renamed | Summary::Copied | Summary::IntentToAdd => {
+                        changes.push(PathChange {
+                            path,
+                            kind: ChangeKind::Unstaged,
+                            lines: DiffStat::default(),
+                        });
+                    }
+                }
+            }
+            status::Item::TreeIndex(change) => staged.push(change),
+        }
+    }
+
+    for change in staged {
+        if conflicted.contains(change.location()) {
+            continue;
+        }
+        // Gitlinks point at commits in another repository; there is no blob to diff.
+        let lines = if change.entry_mode().is_submodule() {
+            DiffStat::default()
+        } else {
+            index_lines(repo, &change)?
+        };
+        changes.push(PathChange {
+            path: change.location().to_path()?.to_path_buf(),
+            kind: ChangeKind::Staged,
+            lines,
+        });
+    }
+
+    for path in conflicted {
+        changes.push(PathChange {
+            path: path.to_path()?.to_path_buf(),
+            kind: ChangeKind::Conflicted,
+            lines: DiffStat::default(),
+        });
+    }
+    Ok(changes)
+}
+
+fn index_lines(repo: &gix::Repository, change: &gix::diff::index::Change) -> Result<DiffStat> {
+    use gix::diff::index::ChangeRef;
+
+    let (before, after) = match change {
+        ChangeRef::Addition { id, .. } => (None, Some(id.as_ref())),
+        ChangeRef::Deletion { id, .. } => (Some(id.as_ref()), None),
+        ChangeRef::Modification {
+            previous_id, id, ..
+        } => (Some(previous_id.as_ref()), Some(id.as_ref())),
+        ChangeRef::Rewrite { source_id, id, .. } => (Some(source_id.as_ref()), Some(id.as_ref())),
+    };
+    blob_lines(repo, before, after)
+}
+
+fn blob_lines(
+    repo: &gix::Repository,
+    before: Option<&gix::oid>,
+    after: Option<&gix::oid>,
+) -> Result<DiffStat> {
+    let before = match before {
+        Some(id) => repo.find_object(id)?.detach().data,
+        None => Vec::new(),
+    };
+    let after = match after {
+        Some(id) => repo.find_object(id)?.detach().data,
+        None => Vec::new(),
+    };
+    Ok(line_changes(&before, &after))
+}
+
+fn worktree_lines(
+    repo: &gix::Repository,
+    id: gix::ObjectId,
+    rela_path: &BString,
+    summary: Summary,
+) -> Result<DiffStat> {
+    let before = repo.find_object(id)?.detach().data;
+    let after = match (summary, repo.workdir()) {
+        (Summary::Modified, Some(workdir)) => {
+            fs::read(workdir.join(rela_path.to_path()?)).unwrap_or_default()
+        }
+        _ => Vec::new(),
+    };
+    Ok(line_changes(&before, &after))
+}
+
+fn line_changes(before: &[u8], after: &[u8]) -> DiffStat {
+    // Match git's numstat, which reports no line counts for binary files.
+    if is_binary(before) || is_binary(after) {
+        return DiffStat::default();
+    }
+
+    let input = InternedInput::new(before, after);
+    let counter = diff(Algorithm::Histogram, &input, Counter::default());
+    DiffStat {
+        files: 1,
+        additions: counter.insertions,
+        deletions: counter.removals,
+    }
+}
+
+fn is_binary(data: &[u8]) -> bool {
+    data.iter().take(8000).any(|byte| *byte == 0)
+}
@@ -1,11 +1,18 @@
 mod config;
+mod fetch;
 mod git;
+mod vcs;
+mod watch;
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet, VecDeque},
     io,
     path::{Path, PathBuf},
-    sync::mpsc::{self, TryRecvError},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError, TryRecvError},
+    },
     thread,
     time::{Duration, Instant},
 };
@@ -26,15 +33,31 @@ use ratatui::{
     text::{Line, Span},
     widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
 };
-use serde::Serialize;
-use shlex;
-
-use crate::config::{AppConfig, EntryConfig, load_config, save_config};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{AppConfig, EntryConfig, load_cache, load_config, save_cache, save_config},
+    fetch::{FetchScheduler, FetchState, FetchTarget},
+    git::GitBackend,
+    vcs::{VcsKind, VcsRegistry},
+    watch::RepoWatcher,
+};
 
 const MAX_HOTKEYS: usize = 9;
+const MAX_REFRESH_WORKERS: usize = 8;
+/// A refresh job that reports nothing for this long is assumed stuck in a
+/// filesystem call git's own timeout cannot interrupt, e.g. a stat on a dead
+/// network mount.
+const REFRESH_STALL_TIMEOUT: Duration = Duration::from_secs(60);
 const BRANCH_REFRESH: Duration = Duration::from_millis(500);
+const FULL_REFRESH: Duration = Duration::from_secs(60);
 const REFRESH_IDLE: Duration = Duration::from_millis(200);
 const STATUS_TIMEOUT: Duration = Duration::from_secs(3);
+const RECENT_BRANCHES: usize = 10;
+/// Uncommitted changes younger than this are not worth pointing out.
+const DIRTY_SHOWN_AFTER: Duration = Duration::from_secs(60 * 60);
+/// Branch states alone are written to the status cache at most this often.
+const STATUS_CACHE_INTERVAL: Duration = Duration::from_secs(5 * 60);
 
 #[derive(Parser)]
 #[command(
@@ -55,6 +78,9 @@ enum Command {
         /// Output as JSON
         #[arg(long)]
         json: bool,
+        /// Answer from the last saved status instead of querying git
+        #[arg(long)]
+        cached: bool,
     },
     /// Add or update a directory
     Add {
@@ -75,12 +101,77 @@ enum Command {
         /// New editor command
         #[arg(short, long)]
         editor: Option<String>,
+        /// Branch to compare against instead of origin/HEAD ("" to detect)
+        #[arg(long)]
+        default_branch: Option<String>,
+        /// Include the entry in background fetching (true/false)
+        #[arg(long)]
+        fetch: Option<bool>,
     },
     /// Remove an entry by index or path
     Remove {
         /// Entry index (1-based) or path
         target: String,
     },
+    /// List worktrees of an entry's repository
+    Worktrees {
+        /// Entry index (1-based) or path
+        target: String,
+        /// Register worktrees that are not yet entries
+        #[arg(long)]
+        add: bool,
+    },
+    /// List local branches of registered repositories
+    Branches {
+        /// Entry indexes (1-based) or paths; every entry when omitted
+        targets: Vec<String>,
+        /// Only branches merged into the default branch or whose upstream is gone
+        #[arg(long)]
+        merged: bool,
+    },
+    /// Delete local branches merged into the default branch or whose upstream is gone
+    Cleanup {
+        /// Entry indexes (1-based) or paths; every entry when omitted
+        targets: Vec<String>,
+        /// Delete the branches instead of only listing them
+        #[arg(long)]
+        apply: bool,
+        /// Also delete branches whose upstream is gone but are not merged
+        #[arg(long)]
+        force: bool,
+    },
+    /// Check out a branch in an entry; lists recent branches when none is given
+    Switch {
+        /// Entry index (1-based) or path
+        target: String,
+        /// Branch to check out
+        branch: Option<String>,
+        /// Stash uncommitted changes instead of refusing to switch
+        #[arg(long)]
+        stash: bool,
+    },
+    /// Run `git fetch --prune` now
+    Fetch {
+        /// Entry indexes (1-based) or paths; every entry not opted out when omitted
+        targets: Vec<String>,
+    },
+    /// Remove a lock file left behind by a git process that is no longer running
+    Repair {
+        /// Entry index (1-based) or path
+        target: String,
+    },
+    /// List entries whose uncommitted changes or stashes are older than a duration
+    Stale {
+        /// Minimum age, e.g. 12h, 3d or 2w; dirty time counts from when gmux first saw it
+        #[arg(long, default_value = "7d", value_parser = parse_duration)]
+        older_than: i64,
+    },
+    /// Report work that exists only on this machine; fails if any is found
+    Check {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
     /// Launch the editor for an entry
     Open {
         /// Entry index (1-based) or path
@@ -103,23 +194,203 @@ fn main() -> Result<()> {
 struct ListEntry {
     index: usize,
     path: String,
+    vcs: Option<VcsKind>,
     branch: String,
+    head_kind: Option<git::HeadKind>,
+    upstream: Option<git::UpstreamInfo>,
+    status: Option<git::RepoStatus>,
+    operation: Option<git::GitOperation>,
+    last_commit: Option<git::CommitSummary>,
+    base: Option<git::BaseComparison>,
+    submodules: Option<git::SubmoduleSummary>,
+    stashes: Option<git::StashSummary>,
+    last_fetch: Option<i64>,
+    dirty_since: Option<i64>,
+    error: Option<git::GitError>,
+    lock: Option<git::LockFile>,
+    repository: Option<String>,
+    /// Path below the worktree root when the entry is a subdirectory.
+    subpath: Option<String>,
+    linked_worktree: bool,
     editor: Option<String>,
+    cached: bool,
+}
+
+/// What `gmux check` found for one entry.
+#[derive(Serialize)]
+struct CheckReport {
+    index: usize,
+    path: String,
+    risks: Vec<Risk>,
+    /// Set when the entry could not be inspected, which also fails the check.
+    error: Option<String>,
+}
+
+/// Work that would be lost along with this machine.
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Risk {
+    Uncommitted {
+        files: u32,
+    },
+    Untracked {
+        files: u32,
+    },
+    Stashes {
+        count: u32,
+    },
+    NoUpstream {
+        branch: String,
+    },
+    UpstreamGone {
+        branch: String,
+    },
+    Unpushed {
+        branch: String,
+        upstream: String,
+        commits: u32,
+    },
+    /// HEAD is detached at a commit no branch contains.
+    DetachedHead {
+        head: String,
+    },
+    /// Only git branches are inspected, so another VCS's unpushed commits and
+    /// bookmarks may exist without being reported.
+    Unchecked {
+        vcs: VcsKind,
+    },
+}
+
+impl Risk {
+    fn describe(&self) -> String {
+        match self {
+            Risk::Uncommitted { files } => format!("{files} file(s) with uncommitted changes"),
+            Risk::Untracked { files } => format!("{files} untracked file(s)"),
+            Risk::Stashes { count } => format!("{count} stash(es)"),
+            Risk::NoUpstream { branch } => format!("{branch}: no upstream"),
+            Risk::UpstreamGone { branch } => format!("{branch}: upstream gone, not merged"),
+            Risk::Unpushed {
+                branch,
+                upstream,
+                commits,
+            } => format!("{branch}: {commits} commit(s) not on {upstream}"),
+            Risk::DetachedHead { head } => format!("detached HEAD at {head} is not on any branch"),
+            Risk::Unchecked { vcs } => format!(
+                "{} repository: unpushed commits and bookmarks were not checked",
+                vcs.icon()
+            ),
+        }
+    }
+}
+
+/// Last known branch state per normalized path, persisted so the TUI and
+/// `list --cached` can show something before git has been queried.
+#[derive(Serialize, Deserialize, Default)]
+struct StatusCache {
+    entries: HashMap<PathBuf, BranchState>,
+    /// When each entry was first seen with uncommitted changes; dropped once
+    /// it is seen clean again.
+    #[serde(default)]
+    dirty_since: HashMap<PathBuf, i64>,
+}
+
+impl StatusCache {
+    fn store<'a>(states: impl Iterator<Item = (&'a PathBuf, &'a BranchState, Option<i64>)>) {
+        let mut cache = StatusCache::default();
+        for (path, state, dirty_since) in states {
+            if let Some(since) = dirty_since {
+                cache.dirty_since.insert(path.clone(), since);
+            }
+            if !matches!(state, BranchState::Unknown) {
+                cache.entries.insert(path.clone(), state.clone());
+            }
+        }
+        let _ = save_cache(&cache);
+    }
+
+    /// Queries every entry of `config`, carrying over how long each has been
+    /// dirty from the previous run, and saves the result.
+    fn refresh(config: &AppConfig) -> Self {
+        let mut previous = load_cache::<StatusCache>().dirty_since;
+        let backend = git::backend(config.git_backend);
+        let entries = RefreshJob::new(backend, config.entries.clone()).wait();
+        let dirty_since = entries
+            .iter()
+            .filter_map(|(path, state)| {
+                track_dirty(previous.remove(path), state).map(|since| (path.clone(), since))
+            })
+            .collect();
+
+        let cache = StatusCache {
+            entries,
+            dirty_since,
+        };
+        let _ = save_cache(&cache);
+        cache
+    }
+}
+
+/// When an entry in `state` has been dirty since, given what was known
+/// before the refresh.
+fn track_dirty(previous: Option<i64>, state: &BranchState) -> Option<i64> {
+    match state.info() {
+        Some(info) if info.status.is_dirty() => previous.or_else(|| Some(git::unix_now())),
+        Some(_) => None,
+        // Nothing new is known while the repository cannot be read.
+        None => previous,
+    }
 }
 
 fn run_cli(command: Command) -> Result<()> {
     match command {
-        Command::List { json } => {
+        Command::List { json, cached } => {
             let config = load_config()?;
+            let cache = if cached {
+                load_cache::<StatusCache>()
+            } else {
+                StatusCache::refresh(&config)
+            };
             let entries: Vec<ListEntry> = config
                 .entries
                 .iter()
                 .enumerate()
-                .map(|(idx, entry)| ListEntry {
-                    index: idx + 1,
-                    path: display_path(&entry.path),
-                    branch: branch_state_for(entry).text(),
-                    editor: entry.editor.clone(),
+                .map(|(idx, entry)| {
+                    let normalized_path = normalize(&entry.path);
+                    let state = cache
+                        .entries
+                        .get(&normalized_path)
+                        .cloned()
+                        .unwrap_or(BranchState::Unknown);
+                    let identity = git::repo_identity(&entry.path);
+                    ListEntry {
+                        index: idx + 1,
+                        path: display_path(&entry.path),
+                        vcs: state.info().map(|info| info.vcs),
+                        branch: state.text(),
+                        head_kind: state.info().map(|info| info.head_kind),
+                        upstream: state.info().and_then(|info| info.upstream.clone()),
+                        status: state.info().map(|info| info.status),
+                        operation: state.info().and_then(|info| info.operation.clone()),
+                        last_commit: state.info().and_then(|info| info.last_commit.clone()),
+                        base: state.info().and_then(|info| info.base.clone()),
+                        submodules: state.info().and_then(|info| info.submodules),
+                        stashes: state.info().and_then(|info| info.stashes),
+                        last_fetch: state.info().and_then(|info| info.last_fetch),
+                        dirty_since: cache.dirty_since.get(&normalized_path).copied(),
+                        error: match &state {
+                            BranchState::Failed(err) => Some(err.clone()),
+                            _ => None,
+                        },
+                        lock: state.lock().cloned(),
+                        repository: identity
+                            .as_ref()
+                            .map(|identity| display_path(&identity.common_dir)),
+                        subpath: git::repo_subpath(&normalized_path)
+                            .map(|subpath| subpath.display().to_string()),
+                        linked_worktree: identity.is_some_and(|identity| identity.linked),
+                        editor: entry.editor.clone(),
+                        cached,
+                    }
                 })
                 .collect();
 
@@ -146,8 +417,26 @@ fn run_cli(command: Command) -> Result<()> {
             target,
             path,
             editor,
-        } => edit_entry_cli(target, path, editor),
+            default_branch,
+            fetch,
+        } => edit_entry_cli(target, path, editor, default_branch, fetch),
         Command::Remove { target } => remove_entry_cli(target),
+        Command::Worktrees { target, add } => worktrees_cli(target, add),
+        Command::Branches { targets, merged } => branches_cli(targets, merged),
+        Command::Cleanup {
+            targets,
+            apply,
+            force,
+        } => cleanup_cli(targets, apply, force),
+        Command::Switch {
+            target,
+            branch,
+            stash,
+        } => switch_cli(target, branch, stash),
+        Command::Fetch { targets } => fetch_cli(targets),
+        Command::Repair { target } => repair_cli(target),
+        Command::Stale { older_than } => stale_cli(older_than),
+        Command::Check { json } => check_cli(json),
         Command::Open { target, editor } => open_entry_cli(target, editor),
     }
 }
@@ -157,12 +446,16 @@ fn run_tui() -> Result<()> {
 
     enable_terminal()?;
     let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    app.rewatch_entries();
     app.refresh_branches();
     let mut last_refresh = Instant::now();
+    let mut last_full_refresh = Instant::now();
     let mut last_interaction = Instant::now();
 
     let res = loop {
+        app.poll_watcher();
         app.tick_refresh_worker();
+        app.tick_fetcher();
         app.maybe_clear_status();
         terminal.draw(|f| ui(f, &app))?;
 
@@ -174,12 +467,12 @@ fn run_tui() -> Result<()> {
 
         if refresh_elapsed >= BRANCH_REFRESH && interaction_elapsed < REFRESH_IDLE {
             timeout = REFRESH_IDLE - interaction_elapsed;
-        } else if refresh_elapsed < BRANCH_REFRESH {
-            if let Some(idle_wait) = REFRESH_IDLE.checked_sub(interaction_elapsed) {
-                if idle_wait > Duration::from_millis(0) && idle_wait < timeout {
-                    timeout = idle_wait;
-                }
-            }
+        } else if refresh_elapsed < BRANCH_REFRESH
+            && let Some(idle_wait) = REFRESH_IDLE.checked_sub(interaction_elapsed)
+            && idle_wait > Duration::from_millis(0)
+            && idle_wait < timeout
+        {
+            timeout = idle_wait;
         }
 
         if event::poll(timeout)? {
@@ -195,12 +488,18 @@ fn run_tui() -> Result<()> {
         }
 
         if last_refresh.elapsed() >= BRANCH_REFRESH && last_interaction.elapsed() >= REFRESH_IDLE {
-            app.refresh_branches();
+            if last_full_refresh.elapsed() >= FULL_REFRESH {
+                app.refresh_branches();
+                last_full_refresh = Instant::now();
+            } else {
+                app.poll_unwatched();
+            }
             app.tick_refresh_worker();
             last_refresh = Instant::now();
         }
     };
 
+    app.save_status_cache();
     disable_terminal()?;
     res
 }
@@ -222,83 +521,344 @@ struct Entry {
     config: EntryConfig,
     normalized_path: PathBuf,
     branch: BranchState,
+    /// `branch` was loaded from the status cache and has not been refreshed yet.
+    cached: bool,
+    /// When the entry was first seen with uncommitted changes.
+    dirty_since: Option<i64>,
+    identity: Option<git::RepoIdentity>,
+    /// Where the entry sits below its worktree root, e.g. one service of a
+    /// monorepo; its status only covers this subdirectory.
+    subpath: Option<PathBuf>,
 }
 
 impl Entry {
     fn from_config(config: EntryConfig) -> Self {
         let normalized_path = normalize(&config.path);
+        let identity = git::repo_identity(&normalized_path);
+        let subpath = git::repo_subpath(&normalized_path);
         Self {
             config,
             normalized_path,
             branch: BranchState::Unknown,
+            cached: false,
+            dirty_since: None,
+            identity,
+            subpath,
+        }
+    }
+
+    /// The path as listed: `root:subpath` for entries below a worktree root.
+    fn display_path(&self) -> (String, Option<String>) {
+        match &self.subpath {
+            Some(subpath) => {
+                let depth = subpath.components().count();
+                let root = self.normalized_path.ancestors().nth(depth);
+                (
+                    display_path(root.unwrap_or(&self.normalized_path)),
+                    Some(format!(":{}", subpath.display())),
+                )
+            }
+            None => (display_path(&self.config.path), None),
+        }
+    }
+
+    fn is_linked_worktree(&self) -> bool {
+        self.identity
+            .as_ref()
+            .is_some_and(|identity| identity.linked)
+    }
+
+    /// Whether `other` is the main checkout this linked worktree belongs to.
+    fn is_worktree_of(&self, other: &Entry) -> bool {
+        match (&self.identity, &other.identity) {
+            (Some(mine), Some(theirs)) => {
+                mine.linked && !theirs.linked && mine.common_dir == theirs.common_dir
+            }
+            _ => false,
         }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 struct GitBranchInfo {
+    #[serde(default)]
+    vcs: VcsKind,
     name: String,
-    additions: u32,
-    deletions: u32,
+    #[serde(default)]
+    head_kind: git::HeadKind,
+    status: git::RepoStatus,
+    upstream: Option<git::UpstreamInfo>,
+    operation: Option<git::GitOperation>,
+    last_commit: Option<git::CommitSummary>,
+    base: Option<git::BaseComparison>,
+    submodules: Option<git::SubmoduleSummary>,
+    stashes: Option<git::StashSummary>,
+    #[serde(default)]
+    last_fetch: Option<i64>,
+    /// A lingering lock that a running process still holds, e.g. `index.lock`
+    /// while `git commit` waits for its message.
+    #[serde(default)]
+    lock: Option<git::LockFile>,
 }
 
 impl GitBranchInfo {
-    fn summary(&self) -> String {
-        let mut changes = Vec::new();
-        if self.additions > 0 {
-            changes.push(format!("+{}", self.additions));
+    fn new(vcs: VcsKind, snapshot: git::RepoSnapshot, lock: Option<git::LockFile>) -> Self {
+        Self {
+            vcs,
+            name: snapshot.branch,
+            head_kind: snapshot.head_kind,
+            status: snapshot.status,
+            upstream: snapshot.upstream,
+            operation: snapshot.operation,
+            last_commit: snapshot.last_commit,
+            base: snapshot.base,
+            submodules: snapshot.submodules,
+            stashes: snapshot.stashes,
+            last_fetch: snapshot.last_fetch,
+            lock,
+        }
+    }
+
+    fn display_name(&self) -> String {
+        // Mid-rebase HEAD is detached; show the branch being rebased instead.
+        if let Some(branch) = self.operation.as_ref().and_then(|op| op.branch.as_ref())
+            && self.head_kind != git::HeadKind::Branch
+        {
+            return branch.clone();
+        }
+        self.name.clone()
+    }
+
+    fn name_style(&self) -> Style {
+        let branch = Style::default().fg(Color::Rgb(120, 170, 255));
+        if self
+            .operation
+            .as_ref()
+            .is_some_and(|op| op.branch.is_some())
+        {
+            return branch;
+        }
+        match self.head_kind {
+            git::HeadKind::Branch => branch,
+            git::HeadKind::Tag => Style::default().fg(Color::Yellow),
+            git::HeadKind::RemoteBranch => branch.add_modifier(Modifier::ITALIC),
+            git::HeadKind::Describe | git::HeadKind::Detached => Style::default()
+                .fg(Color::Gray)
+                .add_modifier(Modifier::ITALIC),
+        }
+    }
+
+    fn tracking_text(&self) -> Option<String> {
+        let upstream = self.upstream.as_ref()?;
+        if upstream.gone {
+            return Some("[gone]".to_string());
+        }
+
+        let mut parts = Vec::new();
+        if upstream.ahead > 0 {
+            parts.push(format!("↑{}", upstream.ahead));
         }
-        if self.deletions > 0 {
-            changes.push(format!("-{}", self.deletions));
+        if upstream.behind > 0 {
+            parts.push(format!("↓{}", upstream.behind));
         }
 
-        if changes.is_empty() {
-            self.name.clone()
+        if parts.is_empty() {
+            None
         } else {
-            format!("{} ({})", self.name, changes.join(" "))
+            Some(parts.join(" "))
+        }
+    }
+
+    fn summary(&self) -> String {
+        let mut name = self.display_name();
+        if let Some(operation) = &self.operation {
+            name = format!("[{}] {name}", operation.badge());
+        }
+        if let Some(badge) = self.conflict_badge() {
+            name = format!("{name} [{badge}]");
+        }
+        if let Some(tracking) = self.tracking_text() {
+            name = format!("{name} {tracking}");
+        }
+
+        let changes: Vec<String> = self
+            .change_segments()
+            .into_iter()
+            .map(|(text, _)| text)
+            .collect();
+
+        if !changes.is_empty() {
+            name = format!("{} ({})", name, changes.join(" "));
+        }
+        if let Some(base) = self.base_text() {
+            name = format!("{name} · {base}");
+        }
+        if let Some(submodules) = &self.submodules {
+            name = format!("{name} [{}]", submodules.badge());
+        }
+        if let Some(stashes) = &self.stashes {
+            name = format!("{name} ⚑{}", stashes.count);
+        }
+        if let Some(lock) = &self.lock {
+            name = format!("{name} [{}]", lock_label(lock));
+        }
+        name
+    }
+
+    /// Committed work since the merge-base, e.g. `↑12 +820 -40 vs main`.
+    fn base_text(&self) -> Option<String> {
+        let base = self.base.as_ref()?;
+        let mut text = format!("↑{}", base.commits);
+        if base.diff.additions > 0 {
+            text.push_str(&format!(" +{}", base.diff.additions));
+        }
+        if base.diff.deletions > 0 {
+            text.push_str(&format!(" -{}", base.diff.deletions));
+        }
+        Some(format!("{text} vs {}", base.name))
+    }
+
+    fn change_segments(&self) -> Vec<(String, Color)> {
+        let mut segments = Vec::new();
+        let status = &self.status;
+        if status.staged.files > 0 {
+            segments.push((diff_segment("●", &status.staged), Color::Green));
+        }
+        if status.unstaged.files > 0 {
+            segments.push((diff_segment("✚", &status.unstaged), Color::Yellow));
         }
+        if status.untracked > 0 {
+            segments.push((format!("?{}", status.untracked), Color::DarkGray));
+        }
+        segments
+    }
+
+    /// Unmerged paths need resolving before anything else, so they get a
+    /// badge of their own rather than a segment among the diff counts.
+    fn conflict_badge(&self) -> Option<String> {
+        let count = self.status.conflicted;
+        (count > 0).then(|| format!("✖{count} conflicted"))
     }
 
     fn spans(&self) -> Vec<Span<'_>> {
         let mut spans = Vec::new();
-        spans.push(Span::styled(
-            self.name.clone(),
-            Style::default().fg(Color::Rgb(120, 170, 255)),
-        ));
+        if let Some(operation) = &self.operation {
+            spans.push(Span::styled(
+                format!(" {} ", operation.badge()),
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ));
+            spans.push(Span::raw(" "));
+        }
+        spans.push(Span::styled(self.display_name(), self.name_style()));
 
-        if self.additions > 0 || self.deletions > 0 {
+        if let Some(badge) = self.conflict_badge() {
             spans.push(Span::raw(" "));
-            spans.push(Span::raw("("));
-            let mut need_space = false;
-            if self.additions > 0 {
-                spans.push(Span::styled(
-                    format!("+{}", self.additions),
-                    Style::default().fg(Color::Green),
-                ));
-                need_space = true;
+            spans.push(Span::styled(
+                format!(" {badge} "),
+                Style::default()
+                    .fg(Color::White)
+                    .bg(Color::Red)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+
+        if let Some(upstream) = &self.upstream {
+            if upstream.gone {
+                spans.push(Span::raw(" "));
+                spans.push(Span::styled("[gone]", Style::default().fg(Color::Yellow)));
+            } else {
+                if upstream.ahead > 0 {
+                    spans.push(Span::raw(" "));
+                    spans.push(Span::styled(
+                        format!("↑{}", upstream.ahead),
+                        Style::default().fg(Color::Cyan),
+                    ));
+                }
+                if upstream.behind > 0 {
+                    spans.push(Span::raw(" "));
+                    spans.push(Span::styled(
+                        format!("↓{}", upstream.behind),
+                        Style::default().fg(Color::Magenta),
+                    ));
+                }
             }
-            if self.deletions > 0 {
-                if need_space {
+        }
+
+        let segments = self.change_segments();
+        if !segments.is_empty() {
+            spans.push(Span::raw(" ("));
+            for (idx, (text, color)) in segments.into_iter().enumerate() {
+                if idx > 0 {
                     spans.push(Span::raw(" "));
                 }
-                spans.push(Span::styled(
-                    format!("-{}", self.deletions),
-                    Style::default().fg(Color::Red),
-                ));
+                spans.push(Span::styled(text, Style::default().fg(color)));
             }
             spans.push(Span::raw(")"));
         }
 
+        if let Some(base) = self.base_text() {
+            spans.push(Span::styled(" · ", Style::default().fg(Color::DarkGray)));
+            spans.push(Span::styled(base, Style::default().fg(Color::Gray)));
+        }
+
+        if let Some(submodules) = &self.submodules {
+            let color = if submodules.needs_attention() {
+                Color::Yellow
+            } else {
+                Color::DarkGray
+            };
+            spans.push(Span::raw(" "));
+            spans.push(Span::styled(
+                format!("[{}]", submodules.badge()),
+                Style::default().fg(color),
+            ));
+        }
+
+        if let Some(stashes) = &self.stashes {
+            spans.push(Span::raw(" "));
+            spans.push(Span::styled(
+                format!("⚑{}", stashes.count),
+                Style::default().fg(Color::Magenta),
+            ));
+        }
+
+        if let Some(lock) = &self.lock {
+            spans.push(Span::raw(" "));
+            spans.push(Span::styled(
+                format!(" {} ", lock_label(lock)),
+                Style::default().fg(Color::Black).bg(Color::Yellow),
+            ));
+        }
+
         spans
     }
 }
 
-#[derive(Clone, Debug)]
+fn diff_segment(marker: &str, stat: &git::DiffStat) -> String {
+    let mut text = format!("{marker}{}", stat.files);
+    if stat.additions > 0 {
+        text.push_str(&format!(" +{}", stat.additions));
+    }
+    if stat.deletions > 0 {
+        text.push_str(&format!(" -{}", stat.deletions));
+    }
+    text
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 enum BranchState {
     Unknown,
-    Ready(GitBranchInfo),
+    Ready(Box<GitBranchInfo>),
     Missing,
     NotGit,
+    TimedOut,
+    /// Git ran but could not read the repository.
+    Failed(git::GitError),
+    /// A lock file that outlived the command which created it.
+    Locked(git::LockFile),
     Error(String),
 }
 
@@ -308,36 +868,148 @@ struct BranchUpdate {
     branch: BranchState,
 }
 
+/// Computes branch state for a set of entries on a bounded pool of worker
+/// threads, streaming each result as soon as it is ready. Entries sharing a
+/// working tree are handled together by one worker.
 struct RefreshJob {
-    receiver: mpsc::Receiver<Vec<BranchUpdate>>,
+    receiver: mpsc::Receiver<BranchUpdate>,
+    cancelled: Arc<AtomicBool>,
+    /// Entries not reported yet.
+    remaining: HashSet<PathBuf>,
+    last_update: Instant,
 }
 
 impl RefreshJob {
-    fn new(configs: Vec<EntryConfig>) -> Self {
+    fn new(backend: Arc<dyn GitBackend>, configs: Vec<EntryConfig>) -> Self {
         let (tx, rx) = mpsc::channel();
-        thread::spawn(move || {
-            let updates = configs
-                .into_iter()
-                .map(|config| {
-                    let normalized_path = normalize(&config.path);
-                    let branch = branch_state_for(&config);
-                    BranchUpdate {
-                        normalized_path,
-                        branch,
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let remaining = configs
+            .iter()
+            .map(|config| normalize(&config.path))
+            .collect();
+        let groups = group_by_worktree(configs);
+        let workers = thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .clamp(1, MAX_REFRESH_WORKERS)
+            .min(groups.len().max(1));
+        let queue = Arc::new(Mutex::new(VecDeque::from(groups)));
+        let registry = Arc::new(VcsRegistry::new(backend.clone()));
+
+        for _ in 0..workers {
+            let tx = tx.clone();
+            let queue = queue.clone();
+            let registry = registry.clone();
+            let backend = backend.clone();
+            let flag = cancelled.clone();
+            thread::spawn(move || {
+                loop {
+                    if flag.load(Ordering::Relaxed) {
+                        return;
                     }
-                })
-                .collect();
-            let _ = tx.send(updates);
-        });
-        Self { receiver: rx }
+                    let Some(group) = queue.lock().ok().and_then(|mut q| q.pop_front()) else {
+                        return;
+                    };
+                    for update in branch_states_for(&registry, backend.as_ref(), &group) {
+                        if tx.send(update).is_err() {
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+
+        Self {
+            receiver: rx,
+            cancelled,
+            remaining,
+            last_update: Instant::now(),
+        }
+    }
+
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    fn try_recv(&mut self) -> Result<BranchUpdate, TryRecvError> {
+        let update = self.receiver.try_recv()?;
+        self.received(&update);
+        Ok(update)
+    }
+
+    fn received(&mut self, update: &BranchUpdate) {
+        self.remaining.remove(&update.normalized_path);
+        self.last_update = Instant::now();
+    }
+
+    /// Gives up on a job that stopped making progress, reporting the entries
+    /// it never got to as timed out.
+    fn expire(&mut self) -> Option<Vec<BranchUpdate>> {
+        if self.remaining.is_empty() || self.last_update.elapsed() < REFRESH_STALL_TIMEOUT {
+            return None;
+        }
+        Some(self.abandon())
+    }
+
+    /// Stuck workers are left behind; they exit once their send fails.
+    fn abandon(&mut self) -> Vec<BranchUpdate> {
+        self.cancel();
+        self.remaining
+            .drain()
+            .map(|normalized_path| BranchUpdate {
+                normalized_path,
+                branch: BranchState::TimedOut,
+            })
+            .collect()
     }
 
-    fn try_recv(&self) -> Result<Vec<BranchUpdate>, TryRecvError> {
-        self.receiver.try_recv()
+    /// Blocks until every entry has been computed or the job stalls.
+    fn wait(mut self) -> HashMap<PathBuf, BranchState> {
+        let mut states = HashMap::new();
+        loop {
+            match self.receiver.recv_timeout(REFRESH_STALL_TIMEOUT) {
+                Ok(update) => {
+                    self.received(&update);
+                    states.insert(update.normalized_path, update.branch);
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    let abandoned = self.abandon();
+                    states.extend(
+                        abandoned
+                            .into_iter()
+                            .map(|update| (update.normalized_path, update.branch)),
+                    );
+                    return states;
+                }
+                Err(RecvTimeoutError::Disconnected) => return states,
+            }
+        }
     }
 }
 
 impl BranchState {
+    fn info(&self) -> Option<&GitBranchInfo> {
+        match self {
+            BranchState::Ready(info) => Some(info),
+            _ => None,
+        }
+    }
+
+    /// A lock worth pointing out, whether it replaced the status or not.
+    fn lock(&self) -> Option<&git::LockFile> {
+        match self {
+            BranchState::Locked(lock) => Some(lock),
+            BranchState::Ready(info) => info.lock.as_ref(),
+            _ => None,
+        }
+    }
+
+    fn last_commit_time(&self) -> Option<i64> {
+        self.info()
+            .and_then(|info| info.last_commit.as_ref())
+            .map(|commit| commit.time)
+    }
+
     fn label(&self) -> Vec<Span<'_>> {
         match self {
             BranchState::Unknown => vec![Span::styled("…", Style::default().fg(Color::DarkGray))],
@@ -347,6 +1019,19 @@ impl BranchState {
                 "not a repo",
                 Style::default().fg(Color::Yellow),
             )],
+            BranchState::TimedOut => vec![Span::styled(
+                "timed out",
+                Style::default()
+                    .fg(Color::LightMagenta)
+                    .add_modifier(Modifier::ITALIC),
+            )],
+            BranchState::Failed(err) => {
+                vec![Span::styled(err.label(), Style::default().fg(Color::Red))]
+            }
+            BranchState::Locked(lock) => vec![Span::styled(
+                lock_label(lock),
+                Style::default().fg(Color::Black).bg(Color::Yellow),
+            )],
             BranchState::Error(err) => {
                 vec![Span::styled(err.clone(), Style::default().fg(Color::Red))]
             }
@@ -359,16 +1044,53 @@ impl BranchState {
             BranchState::Ready(info) => info.summary(),
             BranchState::Missing => "missing".to_string(),
             BranchState::NotGit => "not a repo".to_string(),
+            BranchState::TimedOut => "timed out".to_string(),
+            BranchState::Failed(err) => err.label().to_string(),
+            BranchState::Locked(lock) => lock_label(lock),
             BranchState::Error(err) => err.clone(),
         }
     }
 }
 
+fn lock_label(lock: &git::LockFile) -> String {
+    if lock.holders.is_empty() {
+        format!("stale {}", lock.name())
+    } else {
+        format!("{} held", lock.name())
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 enum Mode {
     Normal,
-    Input { flow: FlowKind, step: FlowStep },
-    ConfirmDelete { index: usize },
+    Input {
+        flow: FlowKind,
+        step: FlowStep,
+    },
+    ConfirmDelete {
+        index: usize,
+    },
+    ConfirmWorktrees {
+        index: usize,
+    },
+    Stashes {
+        index: usize,
+    },
+    /// Recent branches of an entry; `selected` indexes `App::recent_branches`.
+    Branches {
+        index: usize,
+        selected: usize,
+    },
+    /// The tree is dirty; asks whether to stash before switching.
+    ConfirmStash {
+        index: usize,
+        selected: usize,
+    },
+    /// Unmerged files of an entry; `selected` indexes `App::conflicts`.
+    Conflicts {
+        index: usize,
+        selected: usize,
+    },
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -406,20 +1128,51 @@ struct App {
     pending_path: Option<PathBuf>,
     editing_index: Option<usize>,
     status: Option<StatusMessage>,
+    backend: Arc<dyn GitBackend>,
     refresh_job: Option<RefreshJob>,
-    refresh_requested: bool,
+    pending_refresh: HashSet<PathBuf>,
+    watcher: Option<RepoWatcher>,
+    sort_by_age: bool,
+    pending_worktrees: Vec<PathBuf>,
+    /// Stashes listed in the [`Mode::Stashes`] popup.
+    stashes: Vec<git::StashEntry>,
+    /// Branches listed in the [`Mode::Branches`] popup.
+    recent_branches: Vec<git::RecentBranch>,
+    /// Absolute paths listed in the [`Mode::Conflicts`] popup.
+    conflicts: Vec<PathBuf>,
+    /// `None` unless background fetching is enabled in the config.
+    fetcher: Option<FetchScheduler>,
+    /// Whether an entry's `dirty_since` changed since the status cache was
+    /// last written.
+    dirty_since_changed: bool,
+    status_cache_saved: Option<Instant>,
     should_quit: bool,
 }
 
 impl App {
     fn new() -> Result<Self> {
         let config = load_config().unwrap_or_default();
+        let mut cache = load_cache::<StatusCache>();
         let entries = config
             .entries
             .iter()
             .cloned()
-            .map(Entry::from_config)
+            .map(|config| {
+                let mut entry = Entry::from_config(config);
+                if let Some(state) = cache.entries.remove(&entry.normalized_path) {
+                    entry.branch = state;
+                    entry.cached = true;
+                }
+                entry.dirty_since = cache.dirty_since.remove(&entry.normalized_path);
+                entry
+            })
             .collect();
+        let backend = git::backend(config.git_backend);
+        let watcher = RepoWatcher::new(backend.clone()).ok();
+        let fetcher = config
+            .fetch
+            .enabled
+            .then(|| FetchScheduler::new(&config.fetch));
 
         Ok(Self {
             config,
@@ -432,8 +1185,18 @@ impl App {
             pending_path: None,
             editing_index: None,
             status: None,
+            backend,
             refresh_job: None,
-            refresh_requested: false,
+            pending_refresh: HashSet::new(),
+            watcher,
+            sort_by_age: false,
+            pending_worktrees: Vec::new(),
+            stashes: Vec::new(),
+            recent_branches: Vec::new(),
+            conflicts: Vec::new(),
+            fetcher,
+            dirty_since_changed: false,
+            status_cache_saved: None,
             should_quit: false,
         })
     }
@@ -448,6 +1211,13 @@ impl App {
             Mode::Normal => self.handle_normal_key(key),
             Mode::Input { flow, step } => self.handle_input_key(flow, step, key),
             Mode::ConfirmDelete { index } => self.handle_confirm_delete(index, key),
+            Mode::ConfirmWorktrees { index } => self.handle_confirm_worktrees(index, key),
+            Mode::Stashes { .. } => self.handle_stashes_key(key),
+            Mode::Branches { index, selected } => self.handle_branches_key(index, selected, key),
+            Mode::ConfirmStash { index, selected } => {
+                self.handle_confirm_stash(index, selected, key)
+            }
+            Mode::Conflicts { index, selected } => self.handle_conflicts_key(index, selected, key),
         }
     }
 
@@ -474,9 +1244,14 @@ impl App {
             KeyCode::Char('e') => self.start_edit_flow(),
             KeyCode::Char('j') => self.move_selection_down(),
             KeyCode::Char('k') => self.move_selection_up(),
+            KeyCode::Char('s') => self.toggle_sort(),
+            KeyCode::Char('w') => self.request_register_worktrees(),
+            KeyCode::Char('z') => self.show_stashes(),
+            KeyCode::Char('b') => self.show_recent_branches(),
+            KeyCode::Char('c') => self.show_conflicts(),
             KeyCode::Char(c @ '1'..='9') => {
-                let idx = (c as u8 - b'1') as usize;
-                if idx < self.entries.len() {
+                let position = (c as u8 - b'1') as usize;
+                if let Some(&idx) = self.display_order().get(position) {
                     self.selected = idx;
                     self.launch_index(idx);
                 }
@@ -493,23 +1268,21 @@ impl App {
     }
 
     fn handle_input_key(&mut self, flow: FlowKind, step: FlowStep, key: KeyEvent) {
-        if key.modifiers.contains(KeyModifiers::CONTROL) {
-            if self.handle_ctrl_input(flow, step, key.code) {
-                return;
-            }
+        if key.modifiers.contains(KeyModifiers::CONTROL)
+            && self.handle_ctrl_input(flow, step, key.code)
+        {
+            return;
         }
 
-        if key.modifiers.contains(KeyModifiers::SUPER) || key.modifiers.contains(KeyModifiers::META)
+        if (key.modifiers.contains(KeyModifiers::SUPER)
+            || key.modifiers.contains(KeyModifiers::META))
+            && self.handle_super_input(key.code)
         {
-            if self.handle_super_input(key.code) {
-                return;
-            }
+            return;
         }
 
-        if key.modifiers.contains(KeyModifiers::ALT) {
-            if self.handle_alt_input(key.code) {
-                return;
-            }
+        if key.modifiers.contains(KeyModifiers::ALT) && self.handle_alt_input(key.code) {
+            return;
         }
 
         match key.code {
@@ -521,10 +1294,10 @@ impl App {
             KeyCode::Right => self.move_cursor_right(),
             KeyCode::Home => self.cursor_to_start(),
             KeyCode::End => self.cursor_to_end(),
-            KeyCode::Char(c) => {
-                if key.modifiers.is_empty() || key.modifiers == KeyModifiers::SHIFT {
-                    self.insert_char(c);
-                }
+            KeyCode::Char(c)
+                if (key.modifiers.is_empty() || key.modifiers == KeyModifiers::SHIFT) =>
+            {
+                self.insert_char(c);
             }
             _ => {}
         }
@@ -597,24 +1370,312 @@ impl App {
         );
     }
 
-    fn cancel_flow(&mut self) {
-        self.mode = Mode::Normal;
-        self.input_buffer.clear();
-        self.input_cursor = 0;
-        self.pending_path = None;
-        self.editing_index = None;
-        self.kill_buffer.clear();
-    }
-
-    fn complete_directory_step(&mut self, flow: FlowKind) -> Result<()> {
-        let raw_path = self.input_buffer.trim();
-        if raw_path.is_empty() {
-            return Err(anyhow!("directory path cannot be empty"));
+    fn request_register_worktrees(&mut self) {
+        if self.entries.is_empty() {
+            return;
         }
+        let idx = self.selected.min(self.entries.len() - 1);
+        let entry = &self.entries[idx];
+        let path_str = display_path(&entry.config.path);
 
-        let path = expand_path(raw_path);
-        let path_display = display_path(&path);
-        if !path.exists() {
+        let worktrees = match self.backend.worktrees(&entry.normalized_path) {
+            Ok(worktrees) => worktrees,
+            Err(err) => {
+                self.set_status(StatusKind::Error, err.to_string());
+                return;
+            }
+        };
+
+        let registered: HashSet<PathBuf> = self
+            .entries
+            .iter()
+            .map(|entry| worktree_key(&entry.normalized_path))
+            .collect();
+        let missing: Vec<PathBuf> = worktrees
+            .into_iter()
+            .filter(|worktree| !worktree.bare && !worktree.prunable)
+            .map(|worktree| normalize(&worktree.path))
+            .filter(|path| !registered.contains(&worktree_key(path)))
+            .collect();
+
+        if missing.is_empty() {
+            self.set_status(
+                StatusKind::Info,
+                format!("No unregistered worktrees for {path_str}"),
+            );
+            return;
+        }
+
+        let count = missing.len();
+        self.pending_worktrees = missing;
+        self.mode = Mode::ConfirmWorktrees { index: idx };
+        self.set_status(
+            StatusKind::Info,
+            format!("Press Enter to register {count} worktree(s) of {path_str} or Esc to cancel"),
+        );
+    }
+
+    fn show_stashes(&mut self) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let idx = self.selected.min(self.entries.len() - 1);
+        let entry = &self.entries[idx];
+        let stashes = self.backend.stashes(&entry.normalized_path);
+        if stashes.is_empty() {
+            let path_str = display_path(&entry.config.path);
+            self.set_status(StatusKind::Info, format!("No stashes in {path_str}"));
+            return;
+        }
+        self.stashes = stashes;
+        self.mode = Mode::Stashes { index: idx };
+    }
+
+    fn handle_stashes_key(&mut self, key: KeyEvent) {
+        if matches!(
+            key.code,
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char('z')
+        ) {
+            self.mode = Mode::Normal;
+            self.stashes.clear();
+        }
+    }
+
+    fn show_recent_branches(&mut self) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let idx = self.selected.min(self.entries.len() - 1);
+        let entry = &self.entries[idx];
+        let branches = self
+            .backend
+            .recent_branches(&entry.normalized_path, RECENT_BRANCHES);
+        if branches.is_empty() {
+            let path_str = display_path(&entry.config.path);
+            self.set_status(
+                StatusKind::Info,
+                format!("No recent branches in {path_str}"),
+            );
+            return;
+        }
+        self.recent_branches = branches;
+        self.mode = Mode::Branches {
+            index: idx,
+            selected: 0,
+        };
+    }
+
+    fn handle_branches_key(&mut self, index: usize, selected: usize, key: KeyEvent) {
+        let last = self.recent_branches.len().saturating_sub(1);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('b') => {
+                self.mode = Mode::Normal;
+                self.recent_branches.clear();
+            }
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.mode = Mode::Branches {
+                    index,
+                    selected: (selected + 1).min(last),
+                };
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.mode = Mode::Branches {
+                    index,
+                    selected: selected.saturating_sub(1),
+                };
+            }
+            KeyCode::Enter => {
+                let Some(entry) = self.entries.get(index) else {
+                    self.mode = Mode::Normal;
+                    return;
+                };
+                match self.backend.has_local_changes(&entry.normalized_path) {
+                    Ok(true) => self.mode = Mode::ConfirmStash { index, selected },
+                    Ok(false) => self.switch_to_recent(index, selected, false),
+                    Err(err) => {
+                        self.set_status(StatusKind::Error, format!("{err:#}"));
+                        self.mode = Mode::Normal;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_confirm_stash(&mut self, index: usize, selected: usize, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('n') => {
+                self.mode = Mode::Branches { index, selected };
+            }
+            KeyCode::Enter | KeyCode::Char('y') => self.switch_to_recent(index, selected, true),
+            _ => {}
+        }
+    }
+
+    fn switch_to_recent(&mut self, index: usize, selected: usize, stash: bool) {
+        self.mode = Mode::Normal;
+        let branches = std::mem::take(&mut self.recent_branches);
+        let (Some(entry), Some(branch)) = (self.entries.get(index), branches.get(selected)) else {
+            return;
+        };
+
+        let path = entry.normalized_path.clone();
+        match switch_entry_branch(self.backend.as_ref(), &path, &branch.name, stash) {
+            Ok(message) => self.set_status(StatusKind::Info, message),
+            Err(err) => self.set_status(StatusKind::Error, format!("{err:#}")),
+        }
+        self.pending_refresh.insert(path);
+        self.start_refresh_job();
+    }
+
+    fn show_conflicts(&mut self) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let idx = self.selected.min(self.entries.len() - 1);
+        let entry = &self.entries[idx];
+        let path_str = display_path(&entry.config.path);
+        let Some(root) = git::worktree_root(&entry.normalized_path) else {
+            self.set_status(StatusKind::Info, format!("No conflicts in {path_str}"));
+            return;
+        };
+
+        let files = match self.backend.conflicted_files(&entry.normalized_path) {
+            Ok(files) => files,
+            Err(err) => {
+                self.set_status(StatusKind::Error, format!("{err:#}"));
+                return;
+            }
+        };
+        let conflicts: Vec<PathBuf> = files
+            .into_iter()
+            .filter(|file| {
+                entry
+                    .subpath
+                    .as_ref()
+                    .is_none_or(|subpath| file.starts_with(subpath))
+            })
+            .map(|file| root.join(file))
+            .collect();
+        if conflicts.is_empty() {
+            self.set_status(StatusKind::Info, format!("No conflicts in {path_str}"));
+            return;
+        }
+        self.conflicts = conflicts;
+        self.mode = Mode::Conflicts {
+            index: idx,
+            selected: 0,
+        };
+    }
+
+    fn handle_conflicts_key(&mut self, index: usize, selected: usize, key: KeyEvent) {
+        let last = self.conflicts.len().saturating_sub(1);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('c') => {
+                self.mode = Mode::Normal;
+                self.conflicts.clear();
+            }
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.mode = Mode::Conflicts {
+                    index,
+                    selected: (selected + 1).min(last),
+                };
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.mode = Mode::Conflicts {
+                    index,
+                    selected: selected.saturating_sub(1),
+                };
+            }
+            KeyCode::Enter => {
+                let files: Vec<PathBuf> =
+                    self.conflicts.get(selected).cloned().into_iter().collect();
+                self.open_conflicts(index, &files);
+            }
+            KeyCode::Char('a') => {
+                let files = self.conflicts.clone();
+                self.open_conflicts(index, &files);
+            }
+            _ => {}
+        }
+    }
+
+    fn open_conflicts(&mut self, index: usize, files: &[PathBuf]) {
+        self.mode = Mode::Normal;
+        self.conflicts.clear();
+        let Some(entry) = self.entries.get(index) else {
+            return;
+        };
+        match launch_editor_on(&entry.config, files) {
+            Ok(()) => {
+                let text = match files {
+                    [file] => format!("Opened {}", display_path(file)),
+                    _ => format!("Opened {} conflicted files", files.len()),
+                };
+                self.set_status(StatusKind::Info, text);
+            }
+            Err(err) => self.set_status(StatusKind::Error, err.to_string()),
+        }
+    }
+
+    fn handle_confirm_worktrees(&mut self, index: usize, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => {
+                self.mode = Mode::Normal;
+                self.pending_worktrees.clear();
+                self.clear_status();
+            }
+            KeyCode::Enter => {
+                let editor = self
+                    .config
+                    .entries
+                    .get(index)
+                    .and_then(|entry| entry.editor.clone());
+                let paths = std::mem::take(&mut self.pending_worktrees);
+                let count = paths.len();
+                self.config
+                    .entries
+                    .extend(paths.into_iter().map(|path| EntryConfig {
+                        path,
+                        editor: editor.clone(),
+                        default_branch: None,
+                        skip_fetch: false,
+                    }));
+                match save_config(&self.config) {
+                    Ok(()) => {
+                        self.sync_entries();
+                        self.refresh_branches();
+                        self.set_status(
+                            StatusKind::Info,
+                            format!("Registered {count} worktree(s)"),
+                        );
+                    }
+                    Err(err) => self.set_status(StatusKind::Error, err.to_string()),
+                }
+                self.mode = Mode::Normal;
+            }
+            _ => {}
+        }
+    }
+
+    fn cancel_flow(&mut self) {
+        self.mode = Mode::Normal;
+        self.input_buffer.clear();
+        self.input_cursor = 0;
+        self.pending_path = None;
+        self.editing_index = None;
+        self.kill_buffer.clear();
+    }
+
+    fn complete_directory_step(&mut self, flow: FlowKind) -> Result<()> {
+        let raw_path = self.input_buffer.trim();
+        if raw_path.is_empty() {
+            return Err(anyhow!("directory path cannot be empty"));
+        }
+
+        let path = expand_path(raw_path);
+        let path_display = display_path(&path);
+        if !path.exists() {
             return Err(anyhow!("{path_display} does not exist"));
         }
         if !path.is_dir() {
@@ -795,10 +1856,10 @@ impl App {
     }
 
     fn editor_prefill(&self, entry: Option<&EntryConfig>) -> String {
-        if let Some(entry) = entry {
-            if let Some(cmd) = &entry.editor {
-                return cmd.clone();
-            }
+        if let Some(entry) = entry
+            && let Some(cmd) = &entry.editor
+        {
+            return cmd.clone();
         }
         if let Some(cmd) = &self.config.default_editor {
             return cmd.clone();
@@ -807,20 +1868,21 @@ impl App {
     }
 
     fn save_entry(&mut self, path: PathBuf, editor: Option<String>) -> Result<()> {
-        let entry = EntryConfig {
-            path: path.clone(),
-            editor: editor.clone(),
-        };
-
         if let Some(existing) = self
             .config
             .entries
             .iter_mut()
             .find(|e| normalize(&e.path) == normalize(&path))
         {
-            *existing = entry.clone();
+            existing.path = path.clone();
+            existing.editor = editor.clone();
         } else {
-            self.config.entries.push(entry.clone());
+            self.config.entries.push(EntryConfig {
+                path: path.clone(),
+                editor: editor.clone(),
+                default_branch: None,
+                skip_fetch: false,
+            });
         }
 
         save_config(&self.config)?;
@@ -849,10 +1911,9 @@ impl App {
             self.config.default_editor = Some(cmd.clone());
         }
 
-        self.config.entries[idx] = EntryConfig {
-            path: path.clone(),
-            editor: editor.clone(),
-        };
+        let entry = &mut self.config.entries[idx];
+        entry.path = path.clone();
+        entry.editor = editor.clone();
 
         save_config(&self.config)?;
         self.sync_entries();
@@ -896,66 +1957,181 @@ impl App {
     }
 
     fn sync_entries(&mut self) {
-        self.entries = self
-            .config
-            .entries
-            .iter()
-            .cloned()
-            .map(Entry::from_config)
-            .collect();
+        // Results for the old entry list are no longer wanted.
+        if let Some(job) = self.refresh_job.take() {
+            job.cancel();
+        }
+        let previous = std::mem::take(&mut self.entries);
+        self.entries = carry_over_entries(previous, &self.config.entries);
+        self.rewatch_entries();
     }
 
     fn refresh_branches(&mut self) {
-        self.refresh_requested = true;
+        self.pending_refresh.extend(
+            self.entries
+                .iter()
+                .map(|entry| entry.normalized_path.clone()),
+        );
+        self.start_refresh_job();
+    }
+
+    /// Queues entries the watcher reported as changed.
+    fn poll_watcher(&mut self) {
+        if let Some(watcher) = &mut self.watcher {
+            self.pending_refresh.extend(watcher.drain_dirty());
+        }
+    }
+
+    /// Queues entries that could not be watched and therefore need polling.
+    fn poll_unwatched(&mut self) {
+        for entry in &self.entries {
+            let watched = self
+                .watcher
+                .as_ref()
+                .is_some_and(|watcher| watcher.is_watched(&entry.normalized_path));
+            if !watched {
+                self.pending_refresh.insert(entry.normalized_path.clone());
+            }
+        }
         self.start_refresh_job();
     }
 
+    fn rewatch_entries(&mut self) {
+        if let Some(watcher) = &mut self.watcher {
+            let paths: Vec<PathBuf> = self
+                .entries
+                .iter()
+                .map(|entry| entry.normalized_path.clone())
+                .collect();
+            watcher.watch_entries(&paths);
+        }
+    }
+
     fn start_refresh_job(&mut self) {
-        if self.refresh_job.is_some() || !self.refresh_requested {
+        if self.refresh_job.is_some() || self.pending_refresh.is_empty() {
             return;
         }
 
         let configs: Vec<EntryConfig> = self
             .entries
             .iter()
+            .filter(|entry| self.pending_refresh.contains(&entry.normalized_path))
             .map(|entry| entry.config.clone())
             .collect();
+        self.pending_refresh.clear();
 
         if configs.is_empty() {
-            self.refresh_requested = false;
             return;
         }
 
-        self.refresh_job = Some(RefreshJob::new(configs));
-        self.refresh_requested = false;
+        self.refresh_job = Some(RefreshJob::new(self.backend.clone(), configs));
     }
 
     fn tick_refresh_worker(&mut self) {
-        let mut completed: Option<Vec<BranchUpdate>> = None;
-        if let Some(job) = &self.refresh_job {
-            match job.try_recv() {
-                Ok(updates) => {
-                    completed = Some(updates);
-                }
-                Err(TryRecvError::Empty) => {}
-                Err(TryRecvError::Disconnected) => {
-                    completed = Some(Vec::new());
+        let mut updates = Vec::new();
+        let mut finished = false;
+        if let Some(job) = &mut self.refresh_job {
+            loop {
+                match job.try_recv() {
+                    Ok(update) => updates.push(update),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        finished = true;
+                        break;
+                    }
                 }
             }
+            if !finished && let Some(expired) = job.expire() {
+                updates.extend(expired);
+                finished = true;
+            }
         }
 
-        if let Some(updates) = completed {
-            if !updates.is_empty() {
-                self.apply_branch_updates(updates);
-            }
+        if !updates.is_empty() {
+            self.apply_branch_updates(updates);
+        }
+        if finished {
             self.refresh_job = None;
+            self.maybe_save_status_cache();
+        }
+
+        if self.refresh_job.is_none() && !self.pending_refresh.is_empty() {
+            self.start_refresh_job();
+        }
+    }
+
+    /// Advances background fetching and queues a refresh of every entry of
+    /// a repository once its fetch has finished.
+    fn tick_fetcher(&mut self) {
+        let Some(fetcher) = &mut self.fetcher else {
+            return;
+        };
+
+        let mut targets: Vec<FetchTarget> = Vec::new();
+        for entry in &self.entries {
+            let Some(identity) = &entry.identity else {
+                continue;
+            };
+            let vcs = entry.branch.info().map(|info| info.vcs);
+            if entry.config.skip_fetch
+                || vcs.is_some_and(|vcs| vcs != VcsKind::Git)
+                || targets
+                    .iter()
+                    .any(|target| target.repo == identity.common_dir)
+            {
+                continue;
+            }
+            targets.push(FetchTarget {
+                repo: identity.common_dir.clone(),
+                path: entry.normalized_path.clone(),
+                last_fetch: entry.branch.info().and_then(|info| info.last_fetch),
+            });
         }
 
-        if self.refresh_job.is_none() && self.refresh_requested {
+        if let Some(repo) = fetcher.tick(&targets) {
+            self.pending_refresh.extend(
+                self.entries
+                    .iter()
+                    .filter(|entry| {
+                        entry
+                            .identity
+                            .as_ref()
+                            .is_some_and(|identity| identity.common_dir == repo)
+                    })
+                    .map(|entry| entry.normalized_path.clone()),
+            );
             self.start_refresh_job();
         }
     }
 
+    fn fetch_state(&self, entry: &Entry) -> Option<&FetchState> {
+        self.fetcher
+            .as_ref()?
+            .state(&entry.identity.as_ref()?.common_dir)
+    }
+
+    /// Writes the status cache right away when how long an entry has been
+    /// dirty changed, and otherwise at most every [`STATUS_CACHE_INTERVAL`]
+    /// rather than after every refresh.
+    fn maybe_save_status_cache(&mut self) {
+        let due = self
+            .status_cache_saved
+            .is_none_or(|saved| saved.elapsed() >= STATUS_CACHE_INTERVAL);
+        if self.dirty_since_changed || due {
+            self.save_status_cache();
+        }
+    }
+
+    fn save_status_cache(&mut self) {
+        StatusCache::store(
+            self.entries
+                .iter()
+                .map(|entry| (&entry.normalized_path, &entry.branch, entry.dirty_since)),
+        );
+        self.dirty_since_changed = false;
+        self.status_cache_saved = Some(Instant::now());
+    }
+
     fn apply_branch_updates(&mut self, updates: Vec<BranchUpdate>) {
         let mut states: HashMap<PathBuf, BranchState> = HashMap::with_capacity(updates.len());
         for update in updates {
@@ -964,27 +2140,95 @@ impl App {
 
         for entry in &mut self.entries {
             if let Some(state) = states.get(&entry.normalized_path) {
+                let dirty_since = track_dirty(entry.dirty_since, state);
+                self.dirty_since_changed |= dirty_since != entry.dirty_since;
+                entry.dirty_since = dirty_since;
                 entry.branch = state.clone();
+                entry.cached = false;
+            }
+        }
+    }
+
+    /// Entry indices in the order they are listed on screen.
+    fn display_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.entries.len()).collect();
+        if self.sort_by_age {
+            // Oldest HEAD commit first so abandoned checkouts float to the top.
+            order.sort_by_key(|&idx| {
+                self.entries[idx]
+                    .branch
+                    .last_commit_time()
+                    .unwrap_or(i64::MAX)
+            });
+        }
+
+        // Pull linked worktrees directly beneath their registered main checkout.
+        let parents: Vec<Option<usize>> = (0..self.entries.len())
+            .map(|idx| self.worktree_parent(idx))
+            .collect();
+        let mut grouped = Vec::with_capacity(order.len());
+        for &idx in &order {
+            if parents[idx].is_some() {
+                continue;
             }
+            grouped.push(idx);
+            grouped.extend(
+                order
+                    .iter()
+                    .copied()
+                    .filter(|&child| parents[child] == Some(idx)),
+            );
+        }
+        grouped
+    }
+
+    /// Index of the registered main checkout that entry `idx` is a linked worktree of.
+    fn worktree_parent(&self, idx: usize) -> Option<usize> {
+        let entry = &self.entries[idx];
+        if !entry.is_linked_worktree() {
+            return None;
         }
+        self.entries
+            .iter()
+            .position(|other| entry.is_worktree_of(other))
+    }
+
+    fn toggle_sort(&mut self) {
+        self.sort_by_age = !self.sort_by_age;
+        let message = if self.sort_by_age {
+            "Sorted by last commit age"
+        } else {
+            "Sorted by registration order"
+        };
+        self.set_status(StatusKind::Info, message.into());
     }
 
     fn move_selection_up(&mut self) {
         if self.entries.is_empty() {
             return;
         }
-        if self.selected == 0 {
-            self.selected = self.entries.len() - 1;
+        let order = self.display_order();
+        let position = order
+            .iter()
+            .position(|&idx| idx == self.selected)
+            .unwrap_or(0);
+        self.selected = if position == 0 {
+            order[order.len() - 1]
         } else {
-            self.selected -= 1;
-        }
+            order[position - 1]
+        };
     }
 
     fn move_selection_down(&mut self) {
         if self.entries.is_empty() {
             return;
         }
-        self.selected = (self.selected + 1) % self.entries.len();
+        let order = self.display_order();
+        let position = order
+            .iter()
+            .position(|&idx| idx == self.selected)
+            .unwrap_or(0);
+        self.selected = order[(position + 1) % order.len()];
     }
 
     fn buffer_len(&self) -> usize {
@@ -1156,34 +2400,110 @@ impl App {
         if !matches!(self.mode, Mode::Normal) {
             return;
         }
-        if let Some(status) = &self.status {
-            if status.created_at.elapsed() >= STATUS_TIMEOUT {
-                self.status = None;
+        if let Some(status) = &self.status
+            && status.created_at.elapsed() >= STATUS_TIMEOUT
+        {
+            self.status = None;
+        }
+    }
+}
+
+/// Entries that can be answered by a single query: the same working tree
+/// (e.g. several services of one monorepo) compared against the same
+/// default branch. Working trees are told apart by their repository and git
+/// dir rather than by path, which symlinks can disguise. Entries outside any
+/// git working tree stand alone.
+fn group_by_worktree(configs: Vec<EntryConfig>) -> Vec<Vec<EntryConfig>> {
+    let mut keys: Vec<Option<(git::RepoIdentity, Option<String>)>> = Vec::new();
+    let mut groups: Vec<Vec<EntryConfig>> = Vec::new();
+    for config in configs {
+        let key = git::repo_identity(&normalize(&config.path))
+            .map(|identity| (identity, config.default_branch.clone()));
+        match keys.iter().position(|other| key.is_some() && *other == key) {
+            Some(idx) => groups[idx].push(config),
+            None => {
+                keys.push(key);
+                groups.push(vec![config]);
             }
         }
     }
+    groups
 }
 
-fn branch_state_for(entry: &EntryConfig) -> BranchState {
-    if !entry.path.exists() {
-        BranchState::Missing
-    } else if !entry.path.is_dir() {
-        BranchState::Error("not a dir".into())
-    } else if !git::is_git_repo(&entry.path) {
-        BranchState::NotGit
-    } else {
-        match git::current_branch(&entry.path) {
-            Ok(branch) => {
-                let diff = git::diff_stat(&entry.path).unwrap_or_default();
-                let info = GitBranchInfo {
-                    name: branch,
-                    additions: diff.additions,
-                    deletions: diff.deletions,
-                };
-                BranchState::Ready(info)
+/// Queries the repository behind `group` once and gives each entry the
+/// status of its own subdirectory.
+fn branch_states_for(
+    registry: &VcsRegistry,
+    backend: &dyn GitBackend,
+    group: &[EntryConfig],
+) -> Vec<BranchUpdate> {
+    let mut shared = None;
+    group
+        .iter()
+        .map(|entry| {
+            let normalized_path = normalize(&entry.path);
+            let branch = if !entry.path.exists() {
+                BranchState::Missing
+            } else if !entry.path.is_dir() {
+                BranchState::Error("not a dir".into())
+            } else {
+                match shared.get_or_insert_with(|| snapshot_for(registry, backend, entry)) {
+                    Err(state) => state.clone(),
+                    Ok((kind, snapshot, lock)) => {
+                        let snapshot = match git::repo_subpath(&normalized_path) {
+                            Some(subpath) if *kind == VcsKind::Git => snapshot.scoped(&subpath),
+                            _ => snapshot.clone(),
+                        };
+                        BranchState::Ready(Box::new(GitBranchInfo::new(
+                            *kind,
+                            snapshot,
+                            lock.clone(),
+                        )))
+                    }
+                }
+            };
+            BranchUpdate {
+                normalized_path,
+                branch,
             }
-            Err(err) => BranchState::Error(err.to_string()),
+        })
+        .collect()
+}
+
+/// The entry's snapshot along with a lock a running process holds on it.
+fn snapshot_for(
+    registry: &VcsRegistry,
+    backend: &dyn GitBackend,
+    entry: &EntryConfig,
+) -> Result<(VcsKind, git::RepoSnapshot, Option<git::LockFile>), BranchState> {
+    let Some(vcs) = registry.detect(&entry.path) else {
+        return Err(BranchState::NotGit);
+    };
+
+    // Only report locks that should have gone away by now; a fresh one
+    // usually belongs to a command that is still running. One a process
+    // still holds, say `git commit` waiting on the editor, leaves the status
+    // readable, so it only replaces the row once nothing holds it.
+    let lock = (vcs.kind() == VcsKind::Git)
+        .then(|| backend.find_lock(&entry.path))
+        .flatten()
+        .filter(git::LockFile::is_lingering);
+    if let Some(lock) = lock.as_ref().filter(|lock| lock.is_stale()) {
+        return Err(BranchState::Locked(lock.clone()));
+    }
+
+    match vcs.snapshot(&entry.path, entry.default_branch.as_deref()) {
+        Ok(snapshot) => Ok((vcs.kind(), snapshot, lock)),
+        Err(err) if err.downcast_ref::<git::CommandTimedOut>().is_some() => {
+            Err(BranchState::TimedOut)
         }
+        Err(err) if vcs.kind() != VcsKind::Git => Err(BranchState::Error(err.to_string())),
+        Err(err) => Err(BranchState::Failed(
+            match err.downcast_ref::<git::GitError>() {
+                Some(err) => err.clone(),
+                None => git::GitError::classify(&entry.path, &format!("{err:#}")),
+            },
+        )),
     }
 }
 
@@ -1217,15 +2537,26 @@ fn add_entry_cli(path: String, editor: Option<String>) -> Result<()> {
         config.entries.push(EntryConfig {
             path: expanded.clone(),
             editor: editor.clone(),
+            default_branch: None,
+            skip_fetch: false,
         });
-        println!("Added {display}");
+        match worktree_parent_of(&config.entries, &expanded) {
+            Some(parent) => println!("Added {display} (worktree of {})", display_path(&parent)),
+            None => println!("Added {display}"),
+        }
     }
 
     save_config(&config)?;
     Ok(())
 }
 
-fn edit_entry_cli(target: String, new_path: Option<String>, editor: Option<String>) -> Result<()> {
+fn edit_entry_cli(
+    target: String,
+    new_path: Option<String>,
+    editor: Option<String>,
+    default_branch: Option<String>,
+    fetch: Option<bool>,
+) -> Result<()> {
     let mut config = load_config()?;
     if config.entries.is_empty() {
         return Err(anyhow!("no entries registered"));
@@ -1234,7 +2565,7 @@ fn edit_entry_cli(target: String, new_path: Option<String>, editor: Option<Strin
     let idx = resolve_target(&config.entries, &target)
         .ok_or_else(|| anyhow!("entry not found: {target}"))?;
 
-    if new_path.is_none() && editor.is_none() {
+    if new_path.is_none() && editor.is_none() && default_branch.is_none() && fetch.is_none() {
         return Err(anyhow!("nothing to update"));
     }
 
@@ -1260,6 +2591,14 @@ fn edit_entry_cli(target: String, new_path: Option<String>, editor: Option<Strin
         entry.editor = normalized;
     }
 
+    if let Some(branch) = default_branch {
+        entry.default_branch = Some(branch.trim().to_string()).filter(|b| !b.is_empty());
+    }
+
+    if let Some(fetch) = fetch {
+        entry.skip_fetch = !fetch;
+    }
+
     let display = display_path(&entry.path);
     config.entries[idx] = entry;
     save_config(&config)?;
@@ -1303,26 +2642,696 @@ fn open_entry_cli(target: String, editor_override: Option<String>) -> Result<()>
     Ok(())
 }
 
-fn resolve_target(entries: &[EntryConfig], target: &str) -> Option<usize> {
-    if let Ok(idx) = target.parse::<usize>() {
-        if idx >= 1 && idx <= entries.len() {
-            return Some(idx - 1);
-        }
+fn worktrees_cli(target: String, add: bool) -> Result<()> {
+    let mut config = load_config()?;
+    if config.entries.is_empty() {
+        return Err(anyhow!("no entries registered"));
     }
 
-    let expanded = expand_path(target.trim());
-    let normalized = normalize(&expanded);
-    entries
+    let idx = resolve_target(&config.entries, &target)
+        .ok_or_else(|| anyhow!("entry not found: {target}"))?;
+    let entry = config.entries[idx].clone();
+    let backend = git::backend(config.git_backend);
+    let worktrees = backend.worktrees(&entry.path)?;
+
+    let registered: HashSet<PathBuf> = config
+        .entries
+        .iter()
+        .map(|entry| worktree_key(&entry.path))
+        .collect();
+
+    let mut added = 0;
+    for worktree in &worktrees {
+        let is_registered = registered.contains(&worktree_key(&worktree.path));
+        let marker = if is_registered { "*" } else { " " };
+        let head = worktree
+            .branch
+            .clone()
+            .unwrap_or_else(|| "detached".to_string());
+        let mut notes = Vec::new();
+        if worktree.main {
+            notes.push("main");
+        }
+        if worktree.locked {
+            notes.push("locked");
+        }
+        if worktree.prunable {
+            notes.push("prunable");
+        }
+        let notes = if notes.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", notes.join(", "))
+        };
+        println!(
+            "{marker} {:<40} {head}{notes}",
+            display_path(&worktree.path)
+        );
+
+        if add && !is_registered && !worktree.bare && !worktree.prunable {
+            config.entries.push(EntryConfig {
+                path: worktree.path.clone(),
+                editor: entry.editor.clone(),
+                default_branch: entry.default_branch.clone(),
+                skip_fetch: entry.skip_fetch,
+            });
+            added += 1;
+        }
+    }
+
+    if add {
+        if added > 0 {
+            save_config(&config)?;
+        }
+        println!("Registered {added} worktree(s)");
+    }
+    Ok(())
+}
+
+/// Local branches of one repository, each with the reason it must be kept
+/// regardless of being merged (checked out somewhere, the default branch).
+struct BranchReport {
+    path: PathBuf,
+    default_branch: Option<git::DefaultBranch>,
+    branches: Vec<(git::LocalBranch, Option<String>)>,
+}
+
+impl BranchReport {
+    fn header(&self) -> String {
+        match &self.default_branch {
+            Some(default) => format!(
+                "{} (default: {})",
+                display_path(&self.path),
+                default.reference
+            ),
+            None => format!("{} (no default branch found)", display_path(&self.path)),
+        }
+    }
+}
+
+fn stale_reason(branch: &git::LocalBranch) -> Option<&'static str> {
+    match (branch.merged, branch.gone) {
+        (true, true) => Some("merged, upstream gone"),
+        (true, false) => Some("merged"),
+        (false, true) => Some("upstream gone"),
+        (false, false) => None,
+    }
+}
+
+/// Collects a report per repository among `targets` (all entries when
+/// empty); worktrees of one repository share branches, so each repository
+/// is reported once.
+fn branch_reports(targets: &[String]) -> Result<Vec<Result<BranchReport>>> {
+    let config = load_config()?;
+    if config.entries.is_empty() {
+        return Err(anyhow!("no entries registered"));
+    }
+
+    let entries = resolve_targets(&config.entries, targets)?;
+
+    let backend = git::backend(config.git_backend);
+    let mut seen = HashSet::new();
+    let mut reports = Vec::new();
+    for entry in entries {
+        let Some(identity) = git::repo_identity(&entry.path) else {
+            continue;
+        };
+        if !seen.insert(identity.common_dir) {
+            continue;
+        }
+        reports.push(branch_report(backend.as_ref(), entry));
+    }
+    Ok(reports)
+}
+
+fn branch_report(backend: &dyn GitBackend, entry: &EntryConfig) -> Result<BranchReport> {
+    let inventory = backend.local_branches(&entry.path, entry.default_branch.as_deref())?;
+
+    let mut kept: HashMap<String, String> = HashMap::new();
+    for worktree in backend.worktrees(&entry.path)? {
+        let place = display_path(&worktree.path);
+        if let Some(branch) = worktree.branch {
+            kept.insert(branch, format!("checked out in {place}"));
+        } else if let Some(branch) = git::find_git_dir(&worktree.path)
+            .and_then(|git_dir| git::in_progress_operation(&git_dir))
+            .and_then(|operation| operation.branch)
+        {
+            kept.insert(branch, format!("in use by an operation in {place}"));
+        }
+    }
+    if let Some(default) = &inventory.default_branch {
+        kept.entry(default.name.clone())
+            .or_insert_with(|| "default branch".to_string());
+    }
+
+    Ok(BranchReport {
+        path: entry.path.clone(),
+        default_branch: inventory.default_branch,
+        branches: inventory
+            .branches
+            .into_iter()
+            .map(|branch| {
+                let reason = kept.get(&branch.name).cloned();
+                (branch, reason)
+            })
+            .collect(),
+    })
+}
+
+fn branches_cli(targets: Vec<String>, merged_only: bool) -> Result<()> {
+    for report in branch_reports(&targets)? {
+        let report = match report {
+            Ok(report) => report,
+            Err(err) => {
+                eprintln!("{err:#}");
+                continue;
+            }
+        };
+
+        println!("{}", report.header());
+        for (branch, kept) in &report.branches {
+            let stale = stale_reason(branch);
+            if merged_only && stale.is_none() {
+                continue;
+            }
+            let notes: Vec<&str> = stale.into_iter().chain(kept.as_deref()).collect();
+            println!("  {:<40} {}", branch.name, notes.join("; "));
+        }
+    }
+    Ok(())
+}
+
+fn cleanup_cli(targets: Vec<String>, apply: bool, force: bool) -> Result<()> {
+    let config = load_config()?;
+    let backend = git::backend(config.git_backend);
+    let mut candidates = 0;
+    let mut deleted = 0;
+    let mut failed = 0;
+
+    for report in branch_reports(&targets)? {
+        let report = match report {
+            Ok(report) => report,
+            Err(err) => {
+                eprintln!("{err:#}");
+                failed += 1;
+                continue;
+            }
+        };
+
+        let stale: Vec<_> = report
+            .branches
+            .iter()
+            .filter_map(|(branch, kept)| Some((branch, stale_reason(branch)?, kept)))
+            .collect();
+        if stale.is_empty() {
+            continue;
+        }
+
+        println!("{}", report.header());
+        for (branch, reason, kept) in stale {
+            let keep_reason = match kept {
+                Some(kept) => Some(kept.clone()),
+                None if !branch.merged && !force => {
+                    Some("not merged; --force deletes it".to_string())
+                }
+                None => None,
+            };
+            if let Some(keep_reason) = keep_reason {
+                println!("  keep    {:<40} {reason}; {keep_reason}", branch.name);
+                continue;
+            }
+
+            candidates += 1;
+            if !apply {
+                println!("  delete  {:<40} {reason}", branch.name);
+                continue;
+            }
+            match backend.delete_branch(&report.path, &branch.name) {
+                Ok(()) => {
+                    deleted += 1;
+                    println!("  deleted {:<40} {reason}", branch.name);
+                }
+                Err(err) => {
+                    failed += 1;
+                    println!("  failed  {:<40} {err}", branch.name);
+                }
+            }
+        }
+    }
+
+    if !apply {
+        if candidates > 0 {
+            println!("Would delete {candidates} branch(es); run with --apply to delete them");
+        } else {
+            println!("Nothing to clean up");
+        }
+    } else {
+        println!("Deleted {deleted} branch(es)");
+    }
+
+    if failed > 0 {
+        return Err(anyhow!("{failed} repository or branch operation(s) failed"));
+    }
+    Ok(())
+}
+
+/// Checks out `branch` in `path`. A dirty tree is refused unless `stash` is
+/// set, in which case the changes are stashed first.
+fn switch_entry_branch(
+    backend: &dyn GitBackend,
+    path: &Path,
+    branch: &str,
+    stash: bool,
+) -> Result<String> {
+    let display = display_path(path);
+    let dirty = backend.has_local_changes(path)?;
+    if dirty && !stash {
+        return Err(anyhow!(
+            "{display} has uncommitted changes; commit or stash them before switching"
+        ));
+    }
+    if dirty {
+        git::stash_push(path, &format!("gmux: before switching to {branch}"))?;
+    }
+
+    match git::switch_branch(path, branch) {
+        Ok(()) if dirty => Ok(format!(
+            "Stashed changes and switched {display} to {branch}"
+        )),
+        Ok(()) => Ok(format!("Switched {display} to {branch}")),
+        Err(err) if dirty => Err(err.context(format!(
+            "failed to switch {display} to {branch}; your changes are in stash@{{0}}"
+        ))),
+        Err(err) => Err(err.context(format!("failed to switch {display} to {branch}"))),
+    }
+}
+
+fn switch_cli(target: String, branch: Option<String>, stash: bool) -> Result<()> {
+    let config = load_config()?;
+    if config.entries.is_empty() {
+        return Err(anyhow!("no entries registered"));
+    }
+
+    let idx = resolve_target(&config.entries, &target)
+        .ok_or_else(|| anyhow!("entry not found: {target}"))?;
+    let path = normalize(&config.entries[idx].path);
+    let backend = git::backend(config.git_backend);
+
+    let Some(branch) = branch else {
+        let branches = backend.recent_branches(&path, RECENT_BRANCHES);
+        if branches.is_empty() {
+            println!("No recent branches in {}", display_path(&path));
+        }
+        for branch in branches {
+            println!("{:<40} {}", branch.name, relative_age(branch.time));
+        }
+        return Ok(());
+    };
+
+    println!(
+        "{}",
+        switch_entry_branch(backend.as_ref(), &path, &branch, stash)?
+    );
+    Ok(())
+}
+
+fn repair_cli(target: String) -> Result<()> {
+    let config = load_config()?;
+    if config.entries.is_empty() {
+        return Err(anyhow!("no entries registered"));
+    }
+
+    let idx = resolve_target(&config.entries, &target)
+        .ok_or_else(|| anyhow!("entry not found: {target}"))?;
+    let path = normalize(&config.entries[idx].path);
+    let backend = git::backend(config.git_backend);
+    let Some(lock) = backend.find_lock(&path) else {
+        println!("No lock files in {}", display_path(&path));
+        return Ok(());
+    };
+
+    let display = display_path(&lock.path);
+    if !lock.holders.is_empty() {
+        let pids: Vec<String> = lock.holders.iter().map(u32::to_string).collect();
+        return Err(anyhow!(
+            "{display} is in use by process(es) {}; not removing it",
+            pids.join(", ")
+        ));
+    }
+    if !lock.is_lingering() {
+        return Err(anyhow!(
+            "{display} was created {}; a git command may still be running, try again shortly",
+            relative_age(lock.since)
+        ));
+    }
+
+    git::remove_stale_lock(backend.as_ref(), &path)?;
+    println!("Removed {display} (left {})", relative_age(lock.since));
+    Ok(())
+}
+
+fn stale_cli(older_than: i64) -> Result<()> {
+    let config = load_config()?;
+    if config.entries.is_empty() {
+        return Err(anyhow!("no entries registered"));
+    }
+
+    let cache = StatusCache::refresh(&config);
+    let now = git::unix_now();
+    let mut found = false;
+    for (idx, entry) in config.entries.iter().enumerate() {
+        let path = normalize(&entry.path);
+        let mut reasons = Vec::new();
+        if let Some(since) = cache.dirty_since.get(&path)
+            && now - since >= older_than
+        {
+            reasons.push(format!("dirty for {}", compact_duration(now - since)));
+        }
+        if let Some(stashes) = cache
+            .entries
+            .get(&path)
+            .and_then(BranchState::info)
+            .and_then(|info| info.stashes)
+            && now - stashes.oldest >= older_than
+        {
+            reasons.push(format!(
+                "{} stash(es), oldest {}",
+                stashes.count,
+                relative_age(stashes.oldest)
+            ));
+        }
+        if reasons.is_empty() {
+            continue;
+        }
+
+        found = true;
+        println!(
+            "{:>2}. {:<40} {}",
+            idx + 1,
+            display_path(&entry.path),
+            reasons.join(" · ")
+        );
+    }
+
+    if !found {
+        println!(
+            "No uncommitted work or stashes older than {}",
+            compact_duration(older_than)
+        );
+    }
+    Ok(())
+}
+
+fn check_cli(json: bool) -> Result<()> {
+    let config = load_config()?;
+    if config.entries.is_empty() {
+        return Err(anyhow!("no entries registered"));
+    }
+
+    let cache = StatusCache::refresh(&config);
+    let backend = git::backend(config.git_backend);
+    // Changes belong to the working tree and stashes and branches to the
+    // repository, so each is reported once, on its first entry.
+    let mut seen_trees = HashSet::new();
+    let mut seen = HashSet::new();
+    let reports: Vec<CheckReport> = config
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(idx, entry)| {
+            let state = cache.entries.get(&normalize(&entry.path));
+            let mut report = CheckReport {
+                index: idx + 1,
+                path: display_path(&entry.path),
+                risks: Vec::new(),
+                error: None,
+            };
+            match state.and_then(BranchState::info) {
+                Some(info) => {
+                    let tree = git::worktree_root(&normalize(&entry.path))
+                        .is_none_or(|root| seen_trees.insert(root.to_path_buf()));
+                    let shared = git::repo_identity(&entry.path)
+                        .is_none_or(|identity| seen.insert(identity.common_dir));
+                    match entry_risks(backend.as_ref(), entry, info, tree, shared) {
+                        Ok(risks) => report.risks = risks,
+                        Err(err) => report.error = Some(format!("{err:#}")),
+                    }
+                }
+                None => {
+                    report.error =
+                        Some(state.map_or_else(|| "not checked".to_string(), BranchState::text))
+                }
+            }
+            report
+        })
+        .collect();
+
+    let failing = reports
+        .iter()
+        .filter(|report| !report.risks.is_empty() || report.error.is_some())
+        .count();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+    } else {
+        for report in &reports {
+            if let Some(error) = &report.error {
+                println!(
+                    "{:>2}. {}: could not check ({error})",
+                    report.index, report.path
+                );
+            } else if !report.risks.is_empty() {
+                println!("{:>2}. {}", report.index, report.path);
+                for risk in &report.risks {
+                    println!("      {}", risk.describe());
+                }
+            }
+        }
+        if failing == 0 {
+            println!(
+                "All {} entries are safe: nothing exists only locally",
+                reports.len()
+            );
+        }
+    }
+
+    if failing > 0 {
+        return Err(anyhow!(
+            "{failing} of {} entries have local-only work or could not be checked",
+            reports.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Everything in `entry` that is not on a remote. `tree` includes what
+/// belongs to its whole working tree, and `shared` what belongs to the whole
+/// repository; nothing is left without either.
+fn entry_risks(
+    backend: &dyn GitBackend,
+    entry: &EntryConfig,
+    info: &GitBranchInfo,
+    tree: bool,
+    shared: bool,
+) -> Result<Vec<Risk>> {
+    let mut risks = Vec::new();
+    if !tree {
+        return Ok(risks);
+    }
+
+    // A monorepo entry's status only covers its subpath; changes anywhere
+    // else in the working tree are just as local.
+    let path = normalize(&entry.path);
+    let status = match git::worktree_root(&path) {
+        Some(root) if info.vcs == VcsKind::Git && root != path => {
+            backend
+                .snapshot(root, entry.default_branch.as_deref())?
+                .status
+        }
+        _ => info.status,
+    };
+    let uncommitted = status.staged.files + status.unstaged.files + status.conflicted;
+    if uncommitted > 0 {
+        risks.push(Risk::Uncommitted { files: uncommitted });
+    }
+    if status.untracked > 0 {
+        risks.push(Risk::Untracked {
+            files: status.untracked,
+        });
+    }
+    if info.vcs == VcsKind::Git
+        && !matches!(
+            info.head_kind,
+            git::HeadKind::Branch | git::HeadKind::RemoteBranch
+        )
+        && !backend.head_on_branch(&entry.path)?
+    {
+        let head = info.last_commit.as_ref().map(|commit| &commit.hash);
+        risks.push(Risk::DetachedHead {
+            head: head.unwrap_or(&info.name).clone(),
+        });
+    }
+    if !shared {
+        return Ok(risks);
+    }
+
+    if let Some(stashes) = info.stashes {
+        risks.push(Risk::Stashes {
+            count: stashes.count,
+        });
+    }
+    if info.vcs != VcsKind::Git {
+        risks.push(Risk::Unchecked { vcs: info.vcs });
+        return Ok(risks);
+    }
+
+    // Branches merged into a remote default branch are safe wherever they
+    // live; merged into a local one, they are only as safe as it is.
+    let inventory = backend.local_branches(&entry.path, entry.default_branch.as_deref())?;
+    let published = inventory
+        .default_branch
+        .as_ref()
+        .is_some_and(|default| default.remote);
+    for branch in inventory.branches {
+        match branch.upstream {
+            _ if published && branch.merged => {}
+            None => risks.push(Risk::NoUpstream {
+                branch: branch.name,
+            }),
+            Some(_) if branch.gone => risks.push(Risk::UpstreamGone {
+                branch: branch.name,
+            }),
+            Some(upstream) if branch.ahead > 0 => risks.push(Risk::Unpushed {
+                branch: branch.name,
+                upstream,
+                commits: branch.ahead,
+            }),
+            Some(_) => {}
+        }
+    }
+    Ok(risks)
+}
+
+fn fetch_cli(targets: Vec<String>) -> Result<()> {
+    let config = load_config()?;
+    if config.entries.is_empty() {
+        return Err(anyhow!("no entries registered"));
+    }
+
+    // Opting out only applies when no entries were named explicitly.
+    let mut entries = resolve_targets(&config.entries, &targets)?;
+    if targets.is_empty() {
+        entries.retain(|entry| !entry.skip_fetch);
+    }
+
+    let mut seen = HashSet::new();
+    let mut failed = 0;
+    for entry in entries {
+        let Some(identity) = git::repo_identity(&entry.path) else {
+            continue;
+        };
+        if !seen.insert(identity.common_dir) {
+            continue;
+        }
+
+        let display = display_path(&entry.path);
+        match git::fetch(&entry.path) {
+            Ok(()) => println!("Fetched {display}"),
+            Err(err) => {
+                failed += 1;
+                println!(
+                    "Failed to fetch {display}: {}",
+                    fetch::failure_message(&err)
+                );
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(anyhow!("{failed} fetch(es) failed"));
+    }
+    Ok(())
+}
+
+/// Identifies the worktree containing `path` by its git dir, so a worktree
+/// counts as registered whether its entry was added through a symlink or a
+/// subdirectory. Paths outside git stand for themselves.
+fn worktree_key(path: &Path) -> PathBuf {
+    git::repo_identity(path).map_or_else(|| normalize(path), |identity| identity.git_dir)
+}
+
+/// Path of the registered main checkout that `path` is a linked worktree of.
+fn worktree_parent_of(entries: &[EntryConfig], path: &Path) -> Option<PathBuf> {
+    let identity = git::repo_identity(path).filter(|identity| identity.linked)?;
+    entries
+        .iter()
+        .find(|entry| {
+            git::repo_identity(&entry.path)
+                .is_some_and(|other| !other.linked && other.common_dir == identity.common_dir)
+        })
+        .map(|entry| entry.path.clone())
+}
+
+fn resolve_target(entries: &[EntryConfig], target: &str) -> Option<usize> {
+    if let Ok(idx) = target.parse::<usize>()
+        && idx >= 1
+        && idx <= entries.len()
+    {
+        return Some(idx - 1);
+    }
+
+    let expanded = expand_path(target.trim());
+    let normalized = normalize(&expanded);
+    entries
         .iter()
         .position(|entry| normalize(&entry.path) == normalized)
 }
 
+/// Entries named by `targets`, or every entry when none are given.
+fn resolve_targets<'a>(
+    entries: &'a [EntryConfig],
+    targets: &[String],
+) -> Result<Vec<&'a EntryConfig>> {
+    if targets.is_empty() {
+        return Ok(entries.iter().collect());
+    }
+    targets
+        .iter()
+        .map(|target| {
+            resolve_target(entries, target)
+                .map(|idx| &entries[idx])
+                .ok_or_else(|| anyhow!("entry not found: {target}"))
+        })
+        .collect()
+}
+
 fn normalize_editor_arg(editor: Option<String>) -> Option<String> {
     editor
         .map(|value| value.trim().to_string())
         .filter(|value| !value.is_empty())
 }
 
+/// Entries for `configs`, keeping the last known state and dirty time of
+/// paths that were already listed so editing the list does not reset them.
+fn carry_over_entries(previous: Vec<Entry>, configs: &[EntryConfig]) -> Vec<Entry> {
+    let mut previous: HashMap<PathBuf, Entry> = previous
+        .into_iter()
+        .map(|entry| (entry.normalized_path.clone(), entry))
+        .collect();
+    configs
+        .iter()
+        .cloned()
+        .map(|config| {
+            let mut entry = Entry::from_config(config);
+            if let Some(old) = previous.remove(&entry.normalized_path) {
+                entry.branch = old.branch;
+                entry.cached = old.cached;
+                entry.dirty_since = old.dirty_since;
+            }
+            entry
+        })
+        .collect()
+}
+
 fn normalize(path: &Path) -> PathBuf {
     if let Ok(canonical) = path.canonicalize() {
         canonical
@@ -1332,19 +3341,25 @@ fn normalize(path: &Path) -> PathBuf {
 }
 
 fn expand_path(input: &str) -> PathBuf {
-    if let Some(stripped) = input.strip_prefix("~/") {
-        if let Some(home) = dirs::home_dir() {
-            return home.join(stripped);
-        }
+    if let Some(stripped) = input.strip_prefix("~/")
+        && let Some(home) = dirs::home_dir()
+    {
+        return home.join(stripped);
     }
     PathBuf::from(input)
 }
 
 fn launch_editor(entry: &EntryConfig) -> Result<()> {
+    launch_editor_on(entry, std::slice::from_ref(&entry.path))
+}
+
+/// Launches the entry's editor on `targets` (files or directories) instead
+/// of the entry itself.
+fn launch_editor_on(entry: &EntryConfig, targets: &[PathBuf]) -> Result<()> {
     let command_string = entry
         .editor
         .clone()
-        .or_else(|| entry_editor_fallback())
+        .or_else(entry_editor_fallback)
         .context("no editor set. provide one in the entry or set QUICKSWITCH_EDITOR/EDITOR")?;
 
     let mut parts = shlex::split(&command_string)
@@ -1357,7 +3372,7 @@ fn launch_editor(entry: &EntryConfig) -> Result<()> {
     let program = parts.remove(0);
     let mut command = std::process::Command::new(&program);
     command.args(parts);
-    command.arg(&entry.path);
+    command.args(targets);
 
     command.spawn().with_context(|| {
         let path_str = display_path(&entry.path);
@@ -1380,6 +3395,57 @@ fn byte_index_at(s: &str, char_idx: usize) -> usize {
         .unwrap_or_else(|| s.len())
 }
 
+/// Formats a Unix timestamp as a compact age such as `3d ago`.
+fn relative_age(timestamp: i64) -> String {
+    match (git::unix_now() - timestamp).max(0) {
+        seconds if seconds < 60 => "just now".to_string(),
+        seconds => format!("{} ago", compact_duration(seconds)),
+    }
+}
+
+/// Formats a number of seconds in its largest whole unit, such as `6d`.
+fn compact_duration(seconds: i64) -> String {
+    const MINUTE: i64 = 60;
+    const HOUR: i64 = 60 * MINUTE;
+    const DAY: i64 = 24 * HOUR;
+    const WEEK: i64 = 7 * DAY;
+    const MONTH: i64 = 30 * DAY;
+    const YEAR: i64 = 365 * DAY;
+
+    match seconds {
+        s if s < MINUTE => format!("{s}s"),
+        s if s < HOUR => format!("{}m", s / MINUTE),
+        s if s < DAY => format!("{}h", s / HOUR),
+        s if s < WEEK => format!("{}d", s / DAY),
+        s if s < MONTH => format!("{}w", s / WEEK),
+        s if s < YEAR => format!("{}mo", s / MONTH),
+        s => format!("{}y", s / YEAR),
+    }
+}
+
+/// Parses a duration such as `90m`, `12h`, `3d` or `2w` into seconds.
+fn parse_duration(text: &str) -> Result<i64, String> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (count, unit) = text.split_at(split);
+    let count: i64 = count
+        .parse()
+        .map_err(|_| format!("expected a duration such as 3d, got `{text}`"))?;
+    let unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" | "" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(format!("unknown unit `{unit}` (use s, m, h, d or w)")),
+    };
+    count
+        .checked_mul(unit)
+        .ok_or_else(|| format!("duration `{text}` is too long"))
+}
+
 fn display_path(path: &Path) -> String {
     if let Some(home) = dirs::home_dir() {
         if let Ok(stripped) = path.strip_prefix(&home) {
@@ -1389,13 +3455,13 @@ fn display_path(path: &Path) -> String {
             return format!("~/{}", stripped.display());
         }
 
-        if let Ok(canonical) = path.canonicalize() {
-            if let Ok(stripped) = canonical.strip_prefix(&home) {
-                if stripped.as_os_str().is_empty() {
-                    return "~".to_string();
-                }
-                return format!("~/{}", stripped.display());
+        if let Ok(canonical) = path.canonicalize()
+            && let Ok(stripped) = canonical.strip_prefix(&home)
+        {
+            if stripped.as_os_str().is_empty() {
+                return "~".to_string();
             }
+            return format!("~/{}", stripped.display());
         }
     }
 
@@ -1425,7 +3491,7 @@ fn ui(frame: &mut Frame, app: &App) {
                 .add_modifier(Modifier::BOLD),
         ),
         Span::styled(
-            "  — numbers open • j/k or ctrl-n/p move • a add • e edit • d delete (enter) • r refresh",
+            "  — numbers open • j/k or ctrl-n/p move • a add • e edit • d delete (enter) • r refresh • s sort • w worktrees • z stashes • b branches • c conflicts",
             Style::default().fg(Color::White),
         ),
     ]))
@@ -1442,22 +3508,33 @@ fn ui(frame: &mut Frame, app: &App) {
         .borders(Borders::ALL)
         .style(base_style);
 
+    let order = app.display_order();
     let list_items: Vec<ListItem> = if app.entries.is_empty() {
         vec![ListItem::new(Line::from(vec![Span::styled(
             "No directories registered yet (press 'a' to add)",
             base_style,
         )]))]
     } else {
-        app.entries
+        order
             .iter()
             .enumerate()
-            .map(|(idx, entry)| {
-                let hotkey = if idx < MAX_HOTKEYS {
-                    format!("{}.", idx + 1)
+            .map(|(position, &idx)| {
+                let entry = &app.entries[idx];
+                let hotkey = if position < MAX_HOTKEYS {
+                    format!("{}.", position + 1)
                 } else {
                     "·".into()
                 };
-                let branch_spans = entry.branch.label();
+                let mut branch_spans = entry.branch.label();
+                if entry.cached {
+                    branch_spans = branch_spans
+                        .into_iter()
+                        .map(|span| {
+                            let style = span.style.add_modifier(Modifier::DIM);
+                            span.style(style)
+                        })
+                        .collect();
+                }
                 let is_selected = idx == app.selected;
                 let hotkey_style = if is_selected {
                     Style::default().fg(Color::Rgb(120, 170, 255))
@@ -1467,12 +3544,52 @@ fn ui(frame: &mut Frame, app: &App) {
 
                 let mut spans = vec![Span::styled(hotkey, hotkey_style)];
                 spans.push(Span::styled(" ", Style::default().fg(Color::White)));
+                let vcs = entry.branch.info().map(|info| info.vcs);
+                let vcs_color = match vcs {
+                    Some(VcsKind::Jujutsu) => Color::LightGreen,
+                    Some(VcsKind::Mercurial) => Color::LightYellow,
+                    Some(VcsKind::Git) | None => Color::DarkGray,
+                };
                 spans.push(Span::styled(
-                    display_path(&entry.config.path),
-                    Style::default().fg(Color::White),
+                    format!("{:<3} ", vcs.map_or("", VcsKind::icon)),
+                    Style::default().fg(vcs_color),
                 ));
+                if app.worktree_parent(idx).is_some() {
+                    spans.push(Span::styled("└ ", Style::default().fg(Color::DarkGray)));
+                }
+                if entry.is_linked_worktree() {
+                    spans.push(Span::styled("⎇ ", Style::default().fg(Color::Cyan)));
+                }
+                let (path, subpath) = entry.display_path();
+                spans.push(Span::styled(path, Style::default().fg(Color::White)));
+                if let Some(subpath) = subpath {
+                    spans.push(Span::styled(subpath, Style::default().fg(Color::Cyan)));
+                }
                 spans.push(Span::styled("  ", Style::default().fg(Color::White)));
                 spans.extend(branch_spans);
+                if let Some(since) = entry.dirty_since
+                    && git::unix_now() - since >= DIRTY_SHOWN_AFTER.as_secs() as i64
+                {
+                    spans.push(Span::styled("  ", Style::default().fg(Color::White)));
+                    spans.push(Span::styled(
+                        format!("dirty for {}", compact_duration(git::unix_now() - since)),
+                        Style::default().fg(Color::Yellow),
+                    ));
+                }
+                if let Some(FetchState::Failed { .. }) = app.fetch_state(entry) {
+                    spans.push(Span::styled("  ", Style::default().fg(Color::White)));
+                    spans.push(Span::styled(
+                        "fetch failed",
+                        Style::default().fg(Color::Red),
+                    ));
+                }
+                if let Some(time) = entry.branch.last_commit_time() {
+                    spans.push(Span::styled("  ", Style::default().fg(Color::White)));
+                    spans.push(Span::styled(
+                        relative_age(time),
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                }
                 if let Some(editor) = &entry.config.editor {
                     spans.push(Span::styled("  ", Style::default().fg(Color::White)));
                     spans.push(Span::styled(
@@ -1498,11 +3615,203 @@ fn ui(frame: &mut Frame, app: &App) {
 
     let mut list_state = ratatui::widgets::ListState::default();
     if !app.entries.is_empty() {
-        list_state.select(Some(app.selected.min(app.entries.len() - 1)));
+        list_state.select(order.iter().position(|&idx| idx == app.selected));
     }
     frame.render_stateful_widget(list, layout[1], &mut list_state);
 
     draw_bottom_panel(frame, layout[2], app, base_style);
+
+    match app.mode {
+        Mode::Stashes { index } => draw_stashes_popup(frame, layout[1], app, index),
+        Mode::Branches { index, selected } | Mode::ConfirmStash { index, selected } => {
+            draw_branches_popup(frame, layout[1], app, index, selected)
+        }
+        Mode::Conflicts { index, selected } => {
+            draw_conflicts_popup(frame, layout[1], app, index, selected)
+        }
+        _ => {}
+    }
+}
+
+/// A `width` x `height` rectangle centred in `area`, clamped to fit.
+fn centered_rect(area: ratatui::prelude::Rect, width: u16, height: u16) -> ratatui::prelude::Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    ratatui::prelude::Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+fn draw_stashes_popup(frame: &mut Frame, area: ratatui::prelude::Rect, app: &App, index: usize) {
+    let path_text = app
+        .entries
+        .get(index)
+        .map(|entry| display_path(&entry.config.path))
+        .unwrap_or_else(|| "<unknown>".to_string());
+    let popup = centered_rect(
+        area,
+        area.width.saturating_sub(8),
+        app.stashes.len() as u16 + 2,
+    );
+
+    let items: Vec<ListItem> = app
+        .stashes
+        .iter()
+        .map(|stash| {
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("stash@{{{}}}", stash.index),
+                    Style::default().fg(Color::Magenta),
+                ),
+                Span::raw("  "),
+                Span::styled(
+                    stash.branch.as_deref().unwrap_or("(no branch)").to_string(),
+                    Style::default().fg(Color::Rgb(120, 170, 255)),
+                ),
+                Span::raw("  "),
+                Span::styled(stash.message.clone(), Style::default().fg(Color::White)),
+                Span::raw("  "),
+                Span::styled(
+                    relative_age(stash.time),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items).block(
+        Block::default()
+            .title(Span::styled(
+                format!("Stashes in {path_text} (Esc to close)"),
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            ))
+            .borders(Borders::ALL),
+    );
+    frame.render_widget(Clear, popup);
+    frame.render_widget(list, popup);
+}
+
+fn draw_branches_popup(
+    frame: &mut Frame,
+    area: ratatui::prelude::Rect,
+    app: &App,
+    index: usize,
+    selected: usize,
+) {
+    let path_text = app
+        .entries
+        .get(index)
+        .map(|entry| display_path(&entry.config.path))
+        .unwrap_or_else(|| "<unknown>".to_string());
+    let popup = centered_rect(
+        area,
+        area.width.saturating_sub(8),
+        app.recent_branches.len() as u16 + 2,
+    );
+
+    let items: Vec<ListItem> = app
+        .recent_branches
+        .iter()
+        .enumerate()
+        .map(|(position, branch)| {
+            let marker = if position == selected { "› " } else { "  " };
+            ListItem::new(Line::from(vec![
+                Span::styled(marker, Style::default().fg(Color::Rgb(120, 170, 255))),
+                Span::styled(
+                    branch.name.clone(),
+                    Style::default().fg(Color::Rgb(120, 170, 255)),
+                ),
+                Span::raw("  "),
+                Span::styled(
+                    relative_age(branch.time),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(Span::styled(
+                    format!("Recent branches in {path_text} (Enter to switch, Esc to close)"),
+                    Style::default()
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
+                ))
+                .borders(Borders::ALL),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+    let mut state = ratatui::widgets::ListState::default();
+    state.select(Some(selected));
+    frame.render_widget(Clear, popup);
+    frame.render_stateful_widget(list, popup, &mut state);
+}
+
+fn draw_conflicts_popup(
+    frame: &mut Frame,
+    area: ratatui::prelude::Rect,
+    app: &App,
+    index: usize,
+    selected: usize,
+) {
+    let root = app
+        .entries
+        .get(index)
+        .and_then(|entry| git::worktree_root(&entry.normalized_path));
+    let path_text = app
+        .entries
+        .get(index)
+        .map(|entry| display_path(&entry.config.path))
+        .unwrap_or_else(|| "<unknown>".to_string());
+    let popup = centered_rect(
+        area,
+        area.width.saturating_sub(8),
+        app.conflicts.len() as u16 + 2,
+    );
+
+    let items: Vec<ListItem> = app
+        .conflicts
+        .iter()
+        .enumerate()
+        .map(|(position, file)| {
+            let marker = if position == selected { "› " } else { "  " };
+            let name = root
+                .and_then(|root| file.strip_prefix(root).ok())
+                .unwrap_or(file);
+            ListItem::new(Line::from(vec![
+                Span::styled(marker, Style::default().fg(Color::Red)),
+                Span::styled(
+                    name.display().to_string(),
+                    Style::default().fg(Color::White),
+                ),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(Span::styled(
+                    format!(
+                        "Conflicts in {path_text} (Enter to open, a to open all, Esc to close)"
+                    ),
+                    Style::default()
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
+                ))
+                .borders(Borders::ALL),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+    let mut state = ratatui::widgets::ListState::default();
+    state.select(Some(selected));
+    frame.render_widget(Clear, popup);
+    frame.render_stateful_widget(list, popup, &mut state);
 }
 
 fn draw_bottom_panel(
@@ -1512,7 +3821,7 @@ fn draw_bottom_panel(
     base_style: Style,
 ) {
     match app.mode {
-        Mode::Normal => {
+        Mode::Normal | Mode::Stashes { .. } | Mode::Branches { .. } | Mode::Conflicts { .. } => {
             let block = Block::default()
                 .title(Span::styled(
                     "Status",
@@ -1523,6 +3832,8 @@ fn draw_bottom_panel(
                 .borders(Borders::ALL)
                 .style(base_style);
 
+            let selected = app.entries.get(app.selected);
+            let fetch_state = selected.and_then(|entry| app.fetch_state(entry));
             let mut lines = Vec::new();
             if let Some(status) = &app.status {
                 let prefix = match status.kind {
@@ -1533,11 +3844,68 @@ fn draw_bottom_panel(
                     prefix,
                     Span::styled(&status.text, base_style),
                 ]));
+            } else if let Some(BranchState::Failed(err)) = selected.map(|entry| &entry.branch) {
+                lines.push(Line::from(vec![
+                    Span::styled("✖ ", base_style.fg(Color::Red)),
+                    Span::styled(err.explanation(), base_style),
+                ]));
+                if let Some(suggestion) = err.suggestion() {
+                    lines.push(Line::from(Span::styled(
+                        suggestion,
+                        base_style.fg(Color::Yellow),
+                    )));
+                }
+            } else if let Some(lock) = selected.and_then(|entry| entry.branch.lock()) {
+                let holders = if lock.holders.is_empty() {
+                    "no process holds it".to_string()
+                } else {
+                    let pids: Vec<String> = lock.holders.iter().map(u32::to_string).collect();
+                    format!("held by pid {}", pids.join(", "))
+                };
+                lines.push(Line::from(vec![
+                    Span::styled("✖ ", base_style.fg(Color::Red)),
+                    Span::styled(
+                        format!(
+                            "{} created {}, {holders}",
+                            display_path(&lock.path),
+                            relative_age(lock.since)
+                        ),
+                        base_style,
+                    ),
+                ]));
+                if lock.holders.is_empty() {
+                    lines.push(Line::from(Span::styled(
+                        format!("Run `gmux repair {}` to remove it", app.selected + 1),
+                        base_style.fg(Color::Yellow),
+                    )));
+                }
+            } else if let Some(FetchState::Failed { at, message }) = fetch_state {
+                lines.push(Line::from(vec![
+                    Span::styled("✖ ", base_style.fg(Color::Red)),
+                    Span::styled(
+                        format!("fetch failed {}: {message}", relative_age(*at)),
+                        base_style,
+                    ),
+                ]));
             } else {
                 lines.push(Line::from(Span::styled(
-                    "Press number to open • j/k or ctrl-n/p move • a add • e edit • d delete (enter to confirm) • q quit",
+                    "Press number to open • j/k or ctrl-n/p move • a add • e edit • d delete (enter to confirm) • s sort • q quit",
                     base_style,
                 )));
+                let fetched = selected
+                    .and_then(|entry| entry.branch.info())
+                    .and_then(|info| info.last_fetch);
+                let fetch_line = match (fetch_state, fetched) {
+                    (Some(FetchState::Fetching), _) => Some("fetching…".to_string()),
+                    (_, Some(time)) => Some(format!("fetched {}", relative_age(time))),
+                    _ => None,
+                };
+                if let Some(text) = fetch_line {
+                    lines.push(Line::from(Span::styled(
+                        text,
+                        base_style.fg(Color::DarkGray),
+                    )));
+                }
             }
 
             let paragraph = Paragraph::new(lines).block(block).style(base_style);
@@ -1659,5 +4027,192 @@ fn draw_bottom_panel(
                 },
             );
         }
+        Mode::ConfirmStash { index, selected } => {
+            let block = Block::default()
+                .title(Span::styled(
+                    "Uncommitted Changes",
+                    Style::default()
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
+                ))
+                .borders(Borders::ALL)
+                .style(base_style);
+
+            frame.render_widget(block, area);
+
+            let path_text = app
+                .entries
+                .get(index)
+                .map(|entry| display_path(&entry.config.path))
+                .unwrap_or_else(|| "<unknown>".to_string());
+            let branch = app
+                .recent_branches
+                .get(selected)
+                .map_or("", |branch| branch.name.as_str());
+
+            let lines = vec![
+                Line::from(Span::styled(
+                    format!(
+                        "{path_text} has uncommitted changes. Stash them and switch to {branch}?"
+                    ),
+                    Style::default().fg(Color::White),
+                )),
+                Line::from(Span::styled(
+                    "Press y/Enter to stash and switch, n/Esc to go back",
+                    base_style.fg(Color::Rgb(150, 150, 150)),
+                )),
+            ];
+
+            let content = Paragraph::new(lines).style(base_style);
+            frame.render_widget(
+                content,
+                ratatui::prelude::Rect {
+                    x: area.x + 1,
+                    y: area.y + 1,
+                    width: area.width.saturating_sub(2),
+                    height: area.height.saturating_sub(2),
+                },
+            );
+        }
+        Mode::ConfirmWorktrees { index } => {
+            let block = Block::default()
+                .title(Span::styled(
+                    "Register Worktrees",
+                    Style::default()
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
+                ))
+                .borders(Borders::ALL)
+                .style(base_style);
+
+            frame.render_widget(block, area);
+
+            let path_text = app
+                .entries
+                .get(index)
+                .map(|entry| display_path(&entry.config.path))
+                .unwrap_or_else(|| "<unknown>".to_string());
+            let worktrees: Vec<String> = app
+                .pending_worktrees
+                .iter()
+                .map(|path| display_path(path))
+                .collect();
+
+            let lines = vec![
+                Line::from(Span::styled(
+                    format!("Register worktrees of {path_text}?"),
+                    Style::default().fg(Color::White),
+                )),
+                Line::from(Span::styled(
+                    worktrees.join(", "),
+                    Style::default().fg(Color::Cyan),
+                )),
+                Line::from(Span::styled(
+                    "Press Enter to confirm or Esc to cancel",
+                    base_style.fg(Color::Rgb(150, 150, 150)),
+                )),
+            ];
+
+            let content = Paragraph::new(lines).style(base_style);
+            frame.render_widget(
+                content,
+                ratatui::prelude::Rect {
+                    x: area.x + 1,
+                    y: area.y + 1,
+                    width: area.width.saturating_sub(2),
+                    height: area.height.saturating_sub(2),
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, process::Command};
+
+    use super::*;
+
+    fn entry_config(path: &str) -> EntryConfig {
+        EntryConfig {
+            path: PathBuf::from(path),
+            editor: None,
+            default_branch: None,
+            skip_fetch: false,
+        }
+    }
+
+    #[test]
+    fn editing_entries_keeps_dirty_times() {
+        let configs = vec![
+            entry_config("/nonexistent/gmux/a"),
+            entry_config("/nonexistent/gmux/b"),
+            entry_config("/nonexistent/gmux/c"),
+        ];
+        let mut entries = carry_over_entries(Vec::new(), &configs);
+        entries[0].dirty_since = Some(100);
+        entries[1].dirty_since = Some(200);
+        entries[1].branch = BranchState::NotGit;
+        entries[1].cached = true;
+
+        // Remove `c`, move `a` and give `b` an editor.
+        let mut edited = vec![
+            entry_config("/nonexistent/gmux/moved"),
+            entry_config("/nonexistent/gmux/b"),
+        ];
+        edited[1].editor = Some("vim".to_string());
+        let entries = carry_over_entries(entries, &edited);
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].dirty_since, None);
+        assert!(matches!(entries[0].branch, BranchState::Unknown));
+        assert_eq!(entries[1].dirty_since, Some(200));
+        assert!(matches!(entries[1].branch, BranchState::NotGit));
+        assert!(entries[1].cached);
+        assert_eq!(entries[1].config.editor.as_deref(), Some("vim"));
+    }
+
+    #[test]
+    fn worktrees_match_through_symlinks_and_subdirectories() {
+        let dir = std::env::temp_dir().join(format!("gmux-worktrees-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let git = |cwd: &Path, args: &[&str]| {
+            let output = Command::new("git")
+                .args(["-c", "user.name=gmux", "-c", "user.email=gmux@example.com"])
+                .args(args)
+                .current_dir(cwd)
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "git {args:?}: {output:?}");
+        };
+        let main = dir.join("main");
+        let linked = dir.join("linked");
+        git(&dir, &["init", "--quiet", "main"]);
+        git(
+            &main,
+            &["commit", "--quiet", "--allow-empty", "-m", "first"],
+        );
+        git(
+            &main,
+            &["worktree", "add", "--quiet", "-b", "topic", "../linked"],
+        );
+        fs::create_dir_all(linked.join("sub")).unwrap();
+        std::os::unix::fs::symlink(&linked, dir.join("alias")).unwrap();
+
+        let key = worktree_key(&linked);
+        assert_eq!(worktree_key(&dir.join("alias")), key);
+        assert_eq!(worktree_key(&linked.join("sub")), key);
+        assert_ne!(worktree_key(&main), key);
+
+        let groups = group_by_worktree(vec![
+            entry_config(dir.join("alias").to_str().unwrap()),
+            entry_config(main.to_str().unwrap()),
+            entry_config(linked.join("sub").to_str().unwrap()),
+        ]);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].len(), 2);
+
+        let _ = fs::remove_dir_all(&dir);
     }
 }
@@ -0,0 +1,154 @@
+mod hg;
+mod jj;
+
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+    sync::Arc,
+};
+
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+use crate::git::{self, DiffStat, GitBackend, RepoSnapshot};
+
+use hg::Mercurial;
+use jj::Jujutsu;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VcsKind {
+    #[default]
+    Git,
+    Jujutsu,
+    Mercurial,
+}
+
+impl VcsKind {
+    /// Column text shown next to each entry.
+    pub fn icon(self) -> &'static str {
+        match self {
+            VcsKind::Git => "git",
+            VcsKind::Jujutsu => "jj",
+            VcsKind::Mercurial => "hg",
+        }
+    }
+}
+
+/// A version control system an entry can be managed by.
+pub trait Vcs: Send + Sync {
+    fn kind(&self) -> VcsKind;
+    /// Top of the working copy containing `path`, if this VCS manages it.
+    fn root(&self, path: &Path) -> Option<PathBuf>;
+    /// `default_branch` is only meaningful to git; others ignore it.
+    fn snapshot(&self, path: &Path, default_branch: Option<&str>) -> Result<RepoSnapshot>;
+}
+
+/// Git through whichever [`GitBackend`] is configured.
+pub struct Git(pub Arc<dyn GitBackend>);
+
+impl Vcs for Git {
+    fn kind(&self) -> VcsKind {
+        VcsKind::Git
+    }
+
+    fn root(&self, path: &Path) -> Option<PathBuf> {
+        match git::worktree_root(path) {
+            Some(root) => Some(root.to_path_buf()),
+            // Bare repositories have no `.git` entry to find.
+            None => self.0.is_git_repo(path).then(|| path.to_path_buf()),
+        }
+    }
+
+    fn snapshot(&self, path: &Path, default_branch: Option<&str>) -> Result<RepoSnapshot> {
+        self.0.snapshot(path, default_branch)
+    }
+}
+
+/// The supported systems, in the order ties are broken: a colocated
+/// Jujutsu repository also has a `.git` directory but should be read as jj.
+pub struct VcsRegistry {
+    systems: Vec<Box<dyn Vcs>>,
+}
+
+impl VcsRegistry {
+    pub fn new(git: Arc<dyn GitBackend>) -> Self {
+        Self {
+            systems: vec![Box::new(Jujutsu), Box::new(Mercurial), Box::new(Git(git))],
+        }
+    }
+
+    /// Picks the system whose working copy most closely encloses `path`.
+    pub fn detect(&self, path: &Path) -> Option<&dyn Vcs> {
+        let mut best: Option<(&dyn Vcs, usize)> = None;
+        for vcs in &self.systems {
+            let Some(root) = vcs.root(path) else {
+                continue;
+            };
+            let depth = root.components().count();
+            if best.is_none_or(|(_, best_depth)| depth > best_depth) {
+                best = Some((vcs.as_ref(), depth));
+            }
+        }
+        best.map(|(vcs, _)| vcs)
+    }
+}
+
+/// Nearest ancestor of `path` containing a `marker` directory.
+fn root_with(path: &Path, marker: &str) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|dir| dir.join(marker).is_dir())
+        .map(Path::to_path_buf)
+}
+
+/// Runs a non-git VCS tool in `path` with the same time budget as git.
+fn run_tool(program: &str, path: &Path, args: &[&str], envs: &[(&str, &str)]) -> Result<Output> {
+    let child = Command::new(program)
+        .args(args)
+        .envs(envs.iter().copied())
+        .current_dir(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to run {program} (is it installed?)"))?;
+
+    let output = git::wait_with_timeout(child, || format!("{program} {}", args.join(" ")))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr.lines().next().unwrap_or("no output").trim();
+        let subcommand = args.iter().find(|arg| !arg.starts_with('-'));
+        return Err(anyhow!(
+            "{program} {} failed: {message}",
+            subcommand.unwrap_or(&"")
+        ));
+    }
+    Ok(output)
+}
+
+/// Parses the summary line of a `--stat` diff, e.g.
+/// `3 files changed, 10 insertions(+), 2 deletions(-)`.
+fn parse_stat_summary(stdout: &[u8]) -> DiffStat {
+    let text = String::from_utf8_lossy(stdout);
+    let mut stat = DiffStat::default();
+    let Some(summary) = text.lines().rev().find(|line| line.contains("changed")) else {
+        return stat;
+    };
+
+    for part in summary.split(',') {
+        let part = part.trim();
+        let count = part
+            .split_whitespace()
+            .next()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0);
+        if part.contains("changed") {
+            stat.files = count;
+        } else if part.contains("insertion") {
+            stat.additions = count;
+        } else if part.contains("deletion") {
+            stat.deletions = count;
+        }
+    }
+    stat
+}
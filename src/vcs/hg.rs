@@ -0,0 +1,100 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use super::{Vcs, VcsKind, parse_stat_summary, root_with, run_tool};
+use crate::git::{CommitSummary, GitOperation, OperationKind, RepoSnapshot, RepoStatus};
+
+const PARENT_TEMPLATE: &str =
+    r"{node|short}\0{desc|firstline}\0{author|person}\0{date|hgdate}\0{branch}\0{activebookmark}\n";
+
+/// Mercurial, read through the `hg` executable.
+pub struct Mercurial;
+
+impl Vcs for Mercurial {
+    fn kind(&self) -> VcsKind {
+        VcsKind::Mercurial
+    }
+
+    fn root(&self, path: &Path) -> Option<PathBuf> {
+        root_with(path, ".hg")
+    }
+
+    fn snapshot(&self, path: &Path, _default_branch: Option<&str>) -> Result<RepoSnapshot> {
+        let output = hg(path, &["log", "-r", ".", "-T", PARENT_TEMPLATE])?;
+        let text = String::from_utf8_lossy(&output.stdout);
+        let fields: Vec<&str> = text.trim_end_matches('\n').split('\0').collect();
+        let field = |idx: usize| fields.get(idx).copied().unwrap_or_default();
+
+        // An active bookmark is the closest thing to a git branch; fall back
+        // to the named branch (`default`) otherwise.
+        let mut snapshot = RepoSnapshot {
+            branch: match field(5) {
+                "" => field(4).to_string(),
+                bookmark => bookmark.to_string(),
+            },
+            ..RepoSnapshot::default()
+        };
+
+        // The null revision (empty repository) has an all-zero node.
+        let node = field(0);
+        if !node.is_empty() && node.bytes().any(|byte| byte != b'0') {
+            snapshot.oid = Some(node.to_string());
+            snapshot.last_commit = field(3)
+                .split_whitespace()
+                .next()
+                .and_then(|time| time.parse().ok())
+                .map(|time| CommitSummary {
+                    hash: node.to_string(),
+                    subject: field(1).to_string(),
+                    author: field(2).to_string(),
+                    time,
+                });
+        }
+
+        let output = hg(path, &["status"])?;
+        snapshot.status = parse_status(&output.stdout);
+        if snapshot.status.unstaged.files > 0 {
+            let output = hg(path, &["diff", "--stat"])?;
+            let lines = parse_stat_summary(&output.stdout);
+            snapshot.status.unstaged.additions = lines.additions;
+            snapshot.status.unstaged.deletions = lines.deletions;
+        }
+
+        if let Some(root) = self.root(path)
+            && root.join(".hg").join("merge").join("state").exists()
+        {
+            snapshot.operation = Some(GitOperation {
+                kind: OperationKind::Merge,
+                branch: None,
+                step: None,
+                total: None,
+            });
+            let output = hg(path, &["resolve", "--list"])?;
+            snapshot.status.conflicted = String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter(|line| line.starts_with("U "))
+                .count() as u32;
+        }
+
+        Ok(snapshot)
+    }
+}
+
+fn hg(path: &Path, args: &[&str]) -> Result<std::process::Output> {
+    // HGPLAIN disables aliases, localisation and other output customisation.
+    run_tool("hg", path, args, &[("HGPLAIN", "1")])
+}
+
+/// Mercurial has no staging area, so every change counts as unstaged.
+fn parse_status(stdout: &[u8]) -> RepoStatus {
+    let mut status = RepoStatus::default();
+    for line in String::from_utf8_lossy(stdout).lines() {
+        match line.as_bytes().first() {
+            Some(b'M' | b'A' | b'R' | b'!') => status.unstaged.files += 1,
+            Some(b'?') => status.untracked += 1,
+            _ => {}
+        }
+    }
+    status
+}
@@ -0,0 +1,117 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use super::{Vcs, VcsKind, parse_stat_summary, root_with, run_tool};
+use crate::git::{CommitSummary, HeadKind, RepoSnapshot};
+
+/// Working-copy commit: change id, commit id, empty and conflict flags.
+const WORKING_COPY_TEMPLATE: &str = r#"change_id.short() ++ "\0" ++ commit_id ++ "\0" ++ if(empty, "1", "0") ++ "\0" ++ if(conflict, "1", "0") ++ "\n""#;
+const COMMIT_TEMPLATE: &str = r#"commit_id.short(7) ++ "\0" ++ description.first_line() ++ "\0" ++ author.name() ++ "\0" ++ committer.timestamp().format("%s") ++ "\n""#;
+const BOOKMARKS_TEMPLATE: &str = r#"local_bookmarks.map(|b| b.name()).join(",") ++ "\n""#;
+
+/// Jujutsu, including repositories colocated with git.
+pub struct Jujutsu;
+
+impl Vcs for Jujutsu {
+    fn kind(&self) -> VcsKind {
+        VcsKind::Jujutsu
+    }
+
+    fn root(&self, path: &Path) -> Option<PathBuf> {
+        root_with(path, ".jj")
+    }
+
+    fn snapshot(&self, path: &Path, _default_branch: Option<&str>) -> Result<RepoSnapshot> {
+        // The first command snapshots the working copy like `jj status`
+        // would; the rest read that snapshot without taking another.
+        let output = jj(path, &["log", "-r", "@", "-T", WORKING_COPY_TEMPLATE])?;
+        let text = String::from_utf8_lossy(&output.stdout);
+        let mut fields = text.trim_end().split('\0');
+        let change_id = fields.next().unwrap_or_default().to_string();
+        let commit_id = fields.next().map(str::to_string);
+        let empty = fields.next() == Some("1");
+        let conflict = fields.next() == Some("1");
+
+        let mut snapshot = RepoSnapshot {
+            oid: commit_id,
+            ..RepoSnapshot::default()
+        };
+
+        // Bookmarks usually sit on an ancestor of the working-copy commit.
+        let output = jj(
+            path,
+            &[
+                "log",
+                "--ignore-working-copy",
+                "-r",
+                "latest(::@ & bookmarks())",
+                "-T",
+                BOOKMARKS_TEMPLATE,
+            ],
+        )?;
+        let bookmarks = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if bookmarks.is_empty() {
+            snapshot.branch = format!("@{change_id}");
+            snapshot.head_kind = HeadKind::Detached;
+        } else {
+            snapshot.branch = bookmarks;
+        }
+
+        if !empty {
+            let output = jj(
+                path,
+                &["diff", "--ignore-working-copy", "-r", "@", "--stat"],
+            )?;
+            // jj has no index: everything in the working-copy commit is
+            // unstaged work.
+            snapshot.status.unstaged = parse_stat_summary(&output.stdout);
+        }
+
+        if conflict {
+            let output = jj(
+                path,
+                &["resolve", "--ignore-working-copy", "--list", "-r", "@"],
+            )?;
+            snapshot.status.conflicted = String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .count() as u32;
+        }
+
+        let output = jj(
+            path,
+            &[
+                "log",
+                "--ignore-working-copy",
+                "-r",
+                "latest(@- ~ root())",
+                "-T",
+                COMMIT_TEMPLATE,
+            ],
+        )?;
+        snapshot.last_commit = parse_commit(&output.stdout);
+
+        Ok(snapshot)
+    }
+}
+
+fn jj(path: &Path, args: &[&str]) -> Result<std::process::Output> {
+    let mut full = vec!["--no-pager", "--color=never"];
+    full.extend_from_slice(args);
+    if args.first() == Some(&"log") {
+        full.push("--no-graph");
+    }
+    run_tool("jj", path, &full, &[])
+}
+
+fn parse_commit(stdout: &[u8]) -> Option<CommitSummary> {
+    let text = String::from_utf8_lossy(stdout);
+    let mut fields = text.trim_end().splitn(4, '\0');
+    Some(CommitSummary {
+        hash: fields.next().filter(|hash| !hash.is_empty())?.to_string(),
+        subject: fields.next()?.to_string(),
+        author: fields.next()?.to_string(),
+        time: fields.next()?.trim().parse().ok()?,
+    })
+}
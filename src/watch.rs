@@ -0,0 +1,303 @@
+use std::{
+    collections::HashSet,
+    ffi::OsStr,
+    fs,
+    path::{Component, Path, PathBuf},
+    sync::{Arc, mpsc},
+};
+
+use anyhow::Result;
+use notify::{
+    Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
+    event::{AccessKind, AccessMode, ModifyKind},
+};
+
+use crate::git::{self, GitBackend};
+
+/// Git-dir subdirectories whose churn never changes what the list shows.
+const IGNORED_GIT_DIRS: &[&str] = &["objects", "logs", "hooks", "info", "lfs"];
+/// The only parts of `.jj` worth reacting to: new operations (bookmark moves,
+/// commits). Everything else is churn, much of it caused by our own snapshots.
+const RELEVANT_JJ_PATHS: &[&str] = &["repo/op_heads"];
+/// Files in `.hg` that change the branch, bookmark or working-copy state.
+const RELEVANT_HG_PATHS: &[&str] = &[
+    "branch",
+    "bookmarks",
+    "bookmarks.current",
+    "dirstate",
+    "merge",
+];
+/// Metadata directories of other VCSs, watched whole and filtered by
+/// [`is_relevant_entry_path`].
+const VCS_DIRS: &[&str] = &[".jj", ".hg"];
+
+struct WatchRoot {
+    entry: PathBuf,
+    git_dir: Option<PathBuf>,
+    /// Every path handed to the watcher for this entry.
+    watches: HashSet<PathBuf>,
+    /// Gitignored directories left unwatched. `None` when the working tree
+    /// is watched recursively instead of directory by directory.
+    ignored: Option<Vec<PathBuf>>,
+}
+
+/// Watches each entry's working tree and git dir, reporting which entries
+/// changed since the last call to [`RepoWatcher::drain_dirty`].
+pub struct RepoWatcher {
+    watcher: RecommendedWatcher,
+    events: mpsc::Receiver<notify::Result<Event>>,
+    backend: Arc<dyn GitBackend>,
+    roots: Vec<WatchRoot>,
+    watched: HashSet<PathBuf>,
+}
+
+impl RepoWatcher {
+    pub fn new(backend: Arc<dyn GitBackend>) -> Result<Self> {
+        let (tx, rx) = mpsc::channel();
+        let watcher = notify::recommended_watcher(tx)?;
+        Ok(Self {
+            watcher,
+            events: rx,
+            backend,
+            roots: Vec::new(),
+            watched: HashSet::new(),
+        })
+    }
+
+    /// Replaces the watched set. Entries that cannot be watched (missing,
+    /// unsupported filesystem, inotify limits) are left for polling.
+    pub fn watch_entries(&mut self, entries: &[PathBuf]) {
+        for root in self.roots.drain(..) {
+            for path in &root.watches {
+                let _ = self.watcher.unwatch(path);
+            }
+        }
+        self.watched.clear();
+
+        for entry in entries {
+            if self.roots.iter().any(|root| &root.entry == entry) {
+                continue;
+            }
+
+            let mut root = WatchRoot {
+                entry: entry.clone(),
+                git_dir: git::find_git_dir(entry),
+                watches: HashSet::new(),
+                ignored: None,
+            };
+            if root.watch(&mut self.watcher, self.backend.as_ref()) {
+                self.watched.insert(entry.clone());
+            }
+            self.roots.push(root);
+        }
+    }
+
+    pub fn is_watched(&self, entry: &Path) -> bool {
+        self.watched.contains(entry)
+    }
+
+    pub fn drain_dirty(&mut self) -> HashSet<PathBuf> {
+        let mut dirty = HashSet::new();
+        let mut created = Vec::new();
+        while let Ok(event) = self.events.try_recv() {
+            let Ok(event) = event else {
+                continue;
+            };
+            // Reads change nothing, and most of them are our own git calls.
+            if let EventKind::Access(kind) = event.kind
+                && kind != AccessKind::Close(AccessMode::Write)
+            {
+                continue;
+            }
+            let added = matches!(
+                event.kind,
+                EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_))
+            );
+            let removed = matches!(
+                event.kind,
+                EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(_))
+            );
+            for path in &event.paths {
+                for root in &mut self.roots {
+                    if root.is_affected_by(path) {
+                        dirty.insert(root.entry.clone());
+                    }
+                    if removed {
+                        root.watches.retain(|watch| !watch.starts_with(path));
+                    }
+                }
+                if added {
+                    created.push(path.clone());
+                }
+            }
+        }
+
+        for root in &mut self.roots {
+            root.watch_created(&mut self.watcher, self.backend.as_ref(), &created);
+        }
+        dirty
+    }
+}
+
+impl WatchRoot {
+    /// Watches the git dir recursively and the working tree one directory at
+    /// a time, leaving out what git ignores: build output and dependency
+    /// trees can be huge and change constantly without affecting the status.
+    /// Outside git, or if git cannot list ignored paths, the whole entry is
+    /// watched recursively.
+    fn watch(&mut self, watcher: &mut RecommendedWatcher, backend: &dyn GitBackend) -> bool {
+        let Some(git_dir) = self.git_dir.clone() else {
+            return self.add(watcher, &self.entry.clone(), RecursiveMode::Recursive);
+        };
+        let ok = self.add(watcher, &git_dir, RecursiveMode::Recursive);
+        let entry = self.entry.clone();
+        match backend.ignored_dirs(&entry) {
+            Ok(ignored) if !ignored.contains(&entry) => {
+                self.ignored = Some(ignored);
+                ok & self.watch_tree(watcher, &entry)
+            }
+            _ => ok & self.add(watcher, &entry, RecursiveMode::Recursive),
+        }
+    }
+
+    /// Watches `dir` and the directories below it that are not ignored.
+    /// Directories that vanish during the walk are not a failure.
+    fn watch_tree(&mut self, watcher: &mut RecommendedWatcher, dir: &Path) -> bool {
+        if self.is_skipped(dir) {
+            return true;
+        }
+        if dir
+            .file_name()
+            .is_some_and(|name| VCS_DIRS.iter().any(|vcs| name == *vcs))
+        {
+            return self.add(watcher, dir, RecursiveMode::Recursive);
+        }
+        if !self.add(watcher, dir, RecursiveMode::NonRecursive) {
+            return dir != self.entry && !dir.exists();
+        }
+
+        let Ok(children) = fs::read_dir(dir) else {
+            return true;
+        };
+        let mut ok = true;
+        for child in children.flatten() {
+            if child.file_type().is_ok_and(|kind| kind.is_dir()) {
+                ok &= self.watch_tree(watcher, &child.path());
+            }
+        }
+        ok
+    }
+
+    /// Starts watching directories created or moved into the working tree,
+    /// asking git once per new subtree which parts of it are ignored.
+    fn watch_created(
+        &mut self,
+        watcher: &mut RecommendedWatcher,
+        backend: &dyn GitBackend,
+        created: &[PathBuf],
+    ) {
+        if self.ignored.is_none() {
+            return;
+        }
+        let mut dirs: Vec<&PathBuf> = created
+            .iter()
+            .filter(|path| path.starts_with(&self.entry))
+            .filter(|path| !self.watches.contains(*path) && !self.is_skipped(path))
+            .filter(|path| path.is_dir())
+            .collect();
+        // Parents sort before their children, which the walk covers.
+        dirs.sort();
+        dirs.dedup();
+        let mut tops: Vec<&PathBuf> = Vec::new();
+        for dir in dirs {
+            if !tops.iter().any(|top| dir.starts_with(top)) {
+                tops.push(dir);
+            }
+        }
+
+        for dir in tops {
+            if let Ok(ignored) = backend.ignored_dirs(dir)
+                && let Some(known) = &mut self.ignored
+            {
+                known.extend(ignored);
+            }
+            self.watch_tree(watcher, dir);
+        }
+    }
+
+    /// Whether `dir` lies in a git dir, another VCS's metadata or an ignored
+    /// directory, none of which are walked.
+    fn is_skipped(&self, dir: &Path) -> bool {
+        if self
+            .git_dir
+            .as_ref()
+            .is_some_and(|git_dir| dir.starts_with(git_dir))
+        {
+            return true;
+        }
+        if let Ok(relative) = dir.strip_prefix(&self.entry) {
+            let inner = relative.parent().unwrap_or(Path::new(""));
+            let is_metadata =
+                |name: &OsStr| name == ".git" || VCS_DIRS.iter().any(|vcs| name == *vcs);
+            if relative.file_name().is_some_and(|name| name == ".git")
+                || inner.iter().any(is_metadata)
+            {
+                return true;
+            }
+        }
+        self.ignored
+            .iter()
+            .flatten()
+            .any(|ignored| dir.starts_with(ignored))
+    }
+
+    fn add(&mut self, watcher: &mut RecommendedWatcher, path: &Path, mode: RecursiveMode) -> bool {
+        let ok = watcher.watch(path, mode).is_ok();
+        if ok {
+            self.watches.insert(path.to_path_buf());
+        }
+        ok
+    }
+
+    fn is_affected_by(&self, path: &Path) -> bool {
+        if let Some(git_dir) = &self.git_dir
+            && let Ok(relative) = path.strip_prefix(git_dir)
+        {
+            return is_relevant_git_path(relative);
+        }
+        match path.strip_prefix(&self.entry) {
+            Ok(relative) => is_relevant_entry_path(relative),
+            Err(_) => false,
+        }
+    }
+}
+
+/// Filters out the internals of non-git VCS metadata directories.
+fn is_relevant_entry_path(relative: &Path) -> bool {
+    let mut components = relative.components();
+    while let Some(component) = components.next() {
+        let relevant = match component.as_os_str().to_str() {
+            Some(".jj") => RELEVANT_JJ_PATHS,
+            Some(".hg") => RELEVANT_HG_PATHS,
+            _ => continue,
+        };
+        let inner = components.as_path();
+        return relevant.iter().any(|prefix| inner.starts_with(prefix));
+    }
+    true
+}
+
+fn is_relevant_git_path(relative: &Path) -> bool {
+    // Every git write takes locks, and what it writes shows up anyway. Only
+    // the locks shown as an entry's state matter in their own right.
+    if relative
+        .extension()
+        .is_some_and(|extension| extension == "lock")
+    {
+        return git::is_reported_lock(relative);
+    }
+    match relative.components().next() {
+        Some(Component::Normal(first)) => !IGNORED_GIT_DIRS.iter().any(|dir| first == *dir),
+        _ => true,
+    }
+}